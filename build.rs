@@ -6,6 +6,7 @@ use std::process::Command;
 
 fn main() {
     println!("cargo:rerun-if-changed=frontend/");
+    println!("cargo:rerun-if-changed=migrations/");
 
     let npm_output = Command::new("npm")
         .args(&["run", "build"])
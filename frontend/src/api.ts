@@ -1,5 +1,5 @@
 import {GameInfo} from "./components/GameCard.tsx";
-import {AndroidBatteryInfo, GameSession} from "./rust_bindings.ts";
+import {AndroidBatteryInfo} from "./rust_bindings.ts";
 import {AudioDevice} from "./components/AudioSelector.tsx";
 
 export class Api {
@@ -10,7 +10,7 @@ export class Api {
                 return {
                     id: game.id,
                     title: game.title,
-                    cover: Api.GetApiUrl(`/games/${game.id}/cover`),
+                    cover: Api.GetApiUrl(`/games/${game.id}/cover/thumbnail`),
                     playtimeSeconds: game.playtime_sec,
                 } as GameInfo;
             });
@@ -22,10 +22,16 @@ export class Api {
         });
     }
 
-    public static async GetActiveSessionAsync(): Promise<GameSession> {
-        return await fetch(Api.GetApiUrl("/games/active"))
-            .then(res => res.json())
-            .then(json => json as GameSession);
+    public static async SwitchGameAsync(game: GameInfo, token: string): Promise<Response> {
+        return await fetch(Api.GetApiUrl(`/games/${game.id}/switch`) + `?idem_token=${token}`, {
+            method: "POST",
+        });
+    }
+
+    public static async CancelLaunchAsync(launchId: string): Promise<void> {
+        await fetch(Api.GetApiUrl(`/launches/${launchId}/cancel`), {
+            method: "POST",
+        })
     }
 
     public static async KillActiveGameAsync(): Promise<void> {
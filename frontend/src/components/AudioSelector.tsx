@@ -4,6 +4,7 @@ import {Api} from "../api.ts";
 // @ts-ignore
 import {clickOutside} from "./utils/clickOutside.ts";
 import {useWebSocket} from "../socket.ts";
+import {ServerEvent} from "../rust_bindings.ts";
 import {Observable} from "../utils/observable.ts";
 
 export default function AudioSelector() {
@@ -21,14 +22,11 @@ export default function AudioSelector() {
 
     const deviceInfoObservable = new Observable<AudioDevice | null>(null);
 
-    useWebSocket(data => {
-        let parts = (data as string).split(':');
-        let command = parts[0];
-
-        if (command == "default_output_changed" || command == "default_input_changed") {
+    useWebSocket<ServerEvent>(event => {
+        if (event.type == "defaultOutputChanged" || event.type == "defaultInputChanged") {
             refetch()
-        } else if (command == "volume_mute_changed") {
-            deviceInfoObservable.value = JSON.parse(parts.slice(1).join(":")) as AudioDevice;
+        } else if (event.type == "volumeMuteChanged") {
+            deviceInfoObservable.value = event.data;
         }
     });
 
@@ -1,6 +1,6 @@
 import {createResource, createSignal, Show} from "solid-js";
 import {Api} from "../api.ts";
-import {AndroidBatteryInfo} from "../rust_bindings.ts";
+import {AndroidBatteryInfo, ServerEvent} from "../rust_bindings.ts";
 // @ts-ignore
 import {clickOutside} from "./utils/clickOutside.ts";
 import {BatteryFullIcon, BatteryLowIcon, BatteryMediumIcon, BatteryWarningIcon, ChevronDown, CircleAlertIcon} from "lucide-solid";
@@ -15,13 +15,11 @@ export default function BatteryIndicator() {
         setBatteryInfo(await Api.GetDeviceBatteryInfo());
     });
 
-    useWebSocket(data => {
-        let parts = (data as string).split(':');
-        let command = parts[0];
-        let args = parts.slice(1).join(':');
-
-        if (command == "battery") {
-            setBatteryInfo(JSON.parse(args) as AndroidBatteryInfo);
+    useWebSocket<ServerEvent>(event => {
+        if (event.type == "battery") {
+            setBatteryInfo(event.data);
+        } else if (event.type == "snapshot") {
+            setBatteryInfo(event.data.battery);
         }
     });
 
@@ -3,10 +3,167 @@ import type { BatteryChargeSource } from "./BatteryChargeSource";
 import type { BatteryHealth } from "./BatteryHealth";
 import type { BatteryStatus } from "./BatteryStatus";
 
+export type AdbMdnsService = { name: string, address: string, isPairing: boolean, };
+
 export type AndroidBatteryInfo = { stats: AndroidBatteryStats, history: Array<number>, };
 
 export type AndroidBatteryStats = { powerSource: BatteryChargeSource, isWeakCharger: boolean, maxChargeCurrentMa: number, maxChargeVoltageMv: number, chargeCounter: number, status: BatteryStatus, health: BatteryHealth, present: boolean, level: number, scale: number, voltage: number, temperature: number, technology: string, };
 
-export type Game = { id: string, title: string, cover: Array<number> | null, vrBackend: string, steamAppId: bigint | null, protonVersion: string | null, commandLine: string | null, totalPlaytimeSec: number, };
+export type AudioDevice = { id: number, name: string, description: string, is_default: boolean, volume: number, is_muted: boolean, };
+
+export type AudioEndpoint = "inputs" | "outputs";
+
+export type BackendArgChoice = { value: string, label: string, };
+
+export type BackendArgsKind = "none" | "binaryPath" | "envisionProfile" | "prefix";
+
+export type BackendArgsSchema = { kind: BackendArgsKind, required: boolean, description: string, };
+
+export type BackendHealth = { state: BackendHealthState, 
+/**
+ * Restarts during the current game session
+ */
+restartCount: number, error: string | null, };
+
+export type BackendHealthState = "healthy" | "restarting" | "failed";
+
+export type BackendInfo = { name: string, displayName: string, args: BackendArgsSchema, argChoices: Array<BackendArgChoice>, isDetected: boolean, detectionError: string | null, };
+
+export type ClientCommand = { "type": "launchGame", "data": { gameId: string, 
+/**
+ * Wait for the current session to end instead of failing while a game is running
+ */
+queue: boolean, } } | { "type": "switchGame", "data": { gameId: string, } } | { "type": "cancelLaunch", "data": { launchId: string, } } | { "type": "killGame" } | { "type": "setVolume", "data": { deviceId: number, volume: number, muted: boolean, } } | { "type": "setDefaultDevice", "data": { endpoint: AudioEndpoint, deviceId: number, } } | { "type": "reloadBackend" };
+
+/**
+ * A command sent by a client over `/api/sock`; the reply carries the same `request_id`
+ */
+export type ClientMessage = { requestId: string, command: ClientCommand, };
+
+export type CommandAck = { requestId: string, 
+/**
+ * Set for `LaunchGame` and `SwitchGame`, for matching `LaunchProgress` events and cancelling the launch
+ */
+launchId: string | null, };
+
+export type CommandError = { requestId: string, message: string, };
+
+/**
+ * Sent over the socket when a game has exited abnormally, see [`classify_exit`]
+ */
+export type CrashInfo = { gameId: string, kind: CrashKind, exitCode: number | null, exitSignal: number | null, runTimeSecs: bigint, 
+/**
+ * The file name for `/api/diagnostics/{file_name}`, unless the bundle could not be created
+ */
+diagnosticsBundle: string | null, };
+
+export type CrashKind = "nonZeroExit" | "signal" | "earlyExit";
+
+export type Game = { id: string, title: string, vrBackend: string, vrBackendArgs: string, pressureVessel: boolean, steamAppId: bigint | null, protonVersion: string | null, commandLine: string | null, useOverlay: boolean, 
+/**
+ * Runs the game in its own transient systemd user scope, when systemd is available
+ */
+useSystemdScope: boolean, 
+/**
+ * Assigned on the first launch, see `prefix::ensure_prefix_id`
+ */
+prefixId: string | null, 
+/**
+ * Space-separated winetricks verbs that are installed into the prefix before launching
+ */
+winetricksVerbs: string, 
+/**
+ * Steam-style launch options, e.g. `gamemoderun %command% -vr`, see `LaunchOptions`
+ */
+launchOptions: string, 
+/**
+ * The `config.launch` entry Steam games are started with, by index or description.
+ * `None` picks the first entry for the platform, preferring VR entries.
+ */
+steamLaunchConfig: string | null, };
+
+/**
+ * How a killed game has exited, `None` if it is unknown
+ */
+export type GameExitInfo = { exitCode: number | null, exitSignal: number | null, };
+
+export type GameSession = { game: Game, startTimeEpoch: bigint, vrDeviceSerial: string | null, };
+
+export type GameSessionInfo = { game: Game, startTimeEpoch: bigint, vrDeviceSerial: string | null, };
+
+export type GameStats = { gameId: string, sessionCount: bigint, totalPlaytimeSec: bigint, lastPlayedEpoch: bigint | null, };
+
+export type HelloInfo = { protocolVersion: number, };
+
+export type ImportCandidate = { steamAppId: number, title: string, needsProton: boolean, suggestedProtonVersion: string | null, };
+
+export type ImportJobStatus = { "state": "idle" } | { "state": "running" } | { "state": "finished", report: ImportReport, } | { "state": "failed", message: string, };
+
+export type ImportReport = { imported: Array<ImportCandidate>, skippedAppIds: Array<number>, };
+
+export type LaunchOutcome = "started" | "failed" | "cancelled" | "interrupted";
+
+export type LaunchProgress = { launchId: string, gameId: string, state: LaunchState, 
+/**
+ * Only set when the launch has failed
+ */
+error: string | null, };
+
+/**
+ * The answer to a retried launch request whose launch has already ended
+ */
+export type LaunchReplay = { launchId: string, outcome: LaunchOutcome, error: string | null, };
+
+export type LaunchStarted = { launchId: string, };
+
+export type LaunchState = "queued" | "preparing" | "installingVerbs" | "startingBackend" | "waitingForHeadset" | "configuringAudio" | "startingOverlay" | "running" | "stopping" | "failed" | "cancelled";
+
+export type PrefixBackup = { fileName: string, sizeBytes: bigint, createdAt: bigint, };
+
+export type PrefixInfo = { prefixId: string, path: string, 
+/**
+ * Proton creates the prefix on the first launch
+ */
+exists: boolean, sizeBytes: bigint, 
+/**
+ * The prefix version Proton has written to the `version` file, e.g. "9.0-201"
+ */
+protonVersion: string | null, createdAt: bigint | null, };
+
+/**
+ * Every message the server pushes over `/api/sock`, serialized as `{ "type": ..., "data": ... }`
+ */
+export type ServerEvent = { "type": "hello", "data": HelloInfo } | { "type": "snapshot", "data": StateSnapshot } | { "type": "battery", "data": AndroidBatteryInfo } | { "type": "active", "data": GameSessionInfo } | { "type": "inactive" } | { "type": "launchProgress", "data": LaunchProgress } | { "type": "defaultInputChanged", "data": AudioDevice } | { "type": "defaultOutputChanged", "data": AudioDevice } | { "type": "volumeMuteChanged", "data": AudioDevice } | { "type": "libraryChanged" } | { "type": "backendHealth", "data": BackendHealth } | { "type": "gameCrashed", "data": CrashInfo } | { "type": "commandAck", "data": CommandAck } | { "type": "commandError", "data": CommandError };
+
+export type Session = { id: number, gameId: string, startTime: bigint, endTime: bigint, exitCode: number | null, exitSignal: number | null, vrBackend: string, deviceSerial: string | null, logArchive: string | null, 
+/**
+ * Only known for games that ran in a systemd scope
+ */
+cpuTimeMs: bigint | null, peakMemoryBytes: bigint | null, 
+/**
+ * Set when the game has exited abnormally, see `CrashKind`
+ */
+crashKind: string | null, diagnosticsBundle: string | null, };
+
+export type SessionPage = { items: Array<Session>, page: bigint, pageSize: bigint, totalCount: bigint, };
+
+export type StateSnapshot = { activeSession: GameSessionInfo | null, launch: LaunchProgress | null, launchQueue: Array<LaunchProgress>, backendHealth: BackendHealth | null, battery: AndroidBatteryInfo | null, defaultInput: AudioDevice | null, defaultOutput: AudioDevice | null, devices: Array<VrDeviceInfo>, };
+
+/**
+ * One entry of an app's `config.launch` section in `appinfo.vdf`
+ */
+export type SteamLaunchConfig = { index: number, executable: string, arguments: string | null, workingDir: string | null, 
+/**
+ * Comma-separated, e.g. "windows" or "linux,macos". Entries without one run everywhere.
+ */
+osList: string | null, 
+/**
+ * e.g. "default", "vr" or "option1"
+ */
+launchType: string | null, description: string | null, 
+/**
+ * Only used on this beta branch of the app
+ */
+betaKey: string | null, };
 
-export type GameSession = { game: Game, startTimeEpoch: bigint, vrDeviceSerial: string, };
+export type VrDeviceInfo = { serial: string, manufacturer: string, productName: string, ipAddress: string | null, networkAddress: string | null, isUsbConnected: boolean, isSelected: boolean, };
@@ -2,7 +2,7 @@ import {GameInfo} from "../components/GameCard.tsx";
 import {createSignal, onCleanup} from "solid-js";
 import {ClockIcon, RefreshCwIcon, XIcon} from "lucide-solid";
 import {Api} from "../api.ts";
-import {GameSession} from "../rust_bindings.ts";
+import {GameSession, ServerEvent} from "../rust_bindings.ts";
 import {useWebSocket} from "../socket.ts";
 
 export type NowPlayingProps = {
@@ -26,11 +26,8 @@ export default function NowPlaying({game, session, onBack}: NowPlayingProps) {
         return Math.floor(now - start);
     }
 
-    useWebSocket(data => {
-        let parts = (data as string).split(':');
-        let command = parts[0];
-
-        if (command == "inactive") {
+    useWebSocket<ServerEvent>(event => {
+        if (event.type == "inactive") {
             onBack();
         }
     });
@@ -1,10 +1,10 @@
 import Header from "../components/Header.tsx";
 import GamesGrid from "./GamesGrid.tsx";
 import {GameInfo} from "../components/GameCard.tsx";
-import {createResource, createSignal, Show} from "solid-js";
+import {createEffect, createResource, createSignal, Show} from "solid-js";
 import NowPlaying from "./NowPlaying.tsx";
 import {Api} from "../api.ts";
-import {GameSession} from "../rust_bindings.ts";
+import {GameSession, LaunchProgress, ServerEvent} from "../rust_bindings.ts";
 import {useWebSocket} from "../socket.ts";
 import Modal, {ErrorModalContents} from "../components/Modal.tsx";
 import {v4 as uuidv4} from 'uuid';
@@ -12,12 +12,16 @@ import OfflineOverlay from "../components/OfflineOverlay.tsx";
 
 type AppState = 'grid' | 'playing';
 
+const isLaunchFinished = (progress: LaunchProgress) => ["running", "failed", "cancelled"].includes(progress.state);
+
 export default function RootView() {
     const [currentState, setCurrentState] = createSignal<AppState>('grid');
     const [selectedGame, setSelectedGame] = createSignal<GameInfo | null>(null);
     const [activeSession, setActiveSession] = createSignal<GameSession | null>(null);
     const [errorModelContent, setErrorModelContent] = createSignal<ErrorModalContents | null>(null);
     const [isGameLoading, setIsGameLoading] = createSignal<boolean>(false);
+    const [isGameRunning, setIsGameRunning] = createSignal<boolean>(false);
+    const [launchingId, setLaunchingId] = createSignal<string | null>(null);
 
     const handleGameClicked = async (game: GameInfo) => {
         if (isGameLoading()) {
@@ -25,16 +29,26 @@ export default function RootView() {
         }
 
         setIsGameLoading(true);
-        let result = await Api.StartGameAsync(game, uuidv4());
-        setIsGameLoading(false);
-
-        if (result.status >= 500) {
+        // A running game is stopped first, instead of the launch failing
+        let result = isGameRunning()
+            ? await Api.SwitchGameAsync(game, uuidv4())
+            : await Api.StartGameAsync(game, uuidv4());
+
+        // The launch continues in the background and reports its progress over the socket
+        if (result.status >= 400) {
+            setIsGameLoading(false);
             setErrorModelContent({
                 title: "Failed to launch instance",
                 text: await result.text(),
             });
         }
     };
+    const handleCancelLaunch = async () => {
+        let launchId = launchingId();
+        if (launchId) {
+            await Api.CancelLaunchAsync(launchId);
+        }
+    };
     const handleBackToGrid = () => {
         setCurrentState('grid');
         setSelectedGame(null);
@@ -42,36 +56,50 @@ export default function RootView() {
     };
     const [games] = createResource(Api.ListGamesAsync);
 
-    createResource(games, async (gamesList) => {
-        if (!gamesList) return null;
-
-        let activeSession = await Api.GetActiveSessionAsync();
-
-        if (!!activeSession.game.id) {
-            let game_info = gamesList.find((g) => g.id === activeSession.game.id);
-            if (game_info) {
-                setCurrentState('playing');
-                setSelectedGame(game_info);
-                setActiveSession(activeSession);
-            }
-        } else {
-            console.warn("Active game not found!")
+    // The session may be known before the games are loaded, e.g. from the snapshot
+    createEffect(() => {
+        let session = activeSession();
+        let game_info = session && games()?.find((g) => g.id === session.game.id);
+        if (game_info) {
+            setCurrentState('playing');
+            setSelectedGame(game_info);
         }
-
-        return activeSession;
     });
 
-    useWebSocket(data => {
-        let parts = (data as string).split(':');
-        let command = parts[0];
-        let args = parts.slice(1).join(':');
+    useWebSocket<ServerEvent>(event => {
+        if (event.type == "snapshot") {
+            // Sent whenever the socket connects, so a freshly opened page starts with the full state
+            let snapshot = event.data;
+            let launch = snapshot.launch && !isLaunchFinished(snapshot.launch) ? snapshot.launch : null;
+            setIsGameRunning(!!snapshot.activeSession);
+            if (snapshot.activeSession) {
+                setActiveSession(snapshot.activeSession);
+            } else {
+                handleBackToGrid();
+            }
+            setLaunchingId(launch?.launchId ?? null);
+            setIsGameLoading(!!launch);
+        } else if (event.type == "active") {
+            setIsGameRunning(true);
+            setActiveSession(event.data);
+        } else if (event.type == "inactive") {
+            setIsGameRunning(false);
+        } else if (event.type == "launchProgress") {
+            if (!isLaunchFinished(event.data)) {
+                setLaunchingId(event.data.launchId);
+            } else if (launchingId() == event.data.launchId) {
+                setLaunchingId(null);
+            }
 
-        if (command == "active") {
-            let session = JSON.parse(args) as GameSession
-            let game_info = games()!.find((g) => g.id === session.game.id);
-            setCurrentState('playing');
-            setSelectedGame(game_info!);
-            setActiveSession(session);
+            if (event.data.state == "running" || event.data.state == "cancelled") {
+                setIsGameLoading(false);
+            } else if (event.data.state == "failed") {
+                setIsGameLoading(false);
+                setErrorModelContent({
+                    title: "Failed to launch instance",
+                    text: event.data.error ?? "Unknown error",
+                });
+            }
         }
     });
 
@@ -89,6 +117,17 @@ export default function RootView() {
             <NowPlaying game={selectedGame()!} onBack={handleBackToGrid} session={activeSession()!}/>
         </Show>
 
+        <Show when={launchingId()}>
+            <div class="fixed bottom-6 left-1/2 -translate-x-1/2 z-40 flex items-center gap-6 pl-8 pr-2 py-2 bg-white/5 backdrop-blur-xl rounded-3xl border border-white/10 shadow-2xl">
+                <span class="text-white/80">Launching...</span>
+                <button
+                    onClick={handleCancelLaunch}
+                    class="px-8 py-3 bg-white/15 hover:bg-white/20 backdrop-blur-sm border border-white/10 rounded-3xl text-white font-medium shadow-lg">
+                    Cancel
+                </button>
+            </div>
+        </Show>
+
         <OfflineOverlay/>
 
         <Modal contents={errorModelContent()} onClose={() => setErrorModelContent(null)}/>
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE sessions
+(
+    id            INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    game_id       TEXT    NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+    start_time    BIGINT  NOT NULL,
+    end_time      BIGINT  NOT NULL,
+    exit_code     INTEGER,
+    exit_signal   INTEGER,
+    vr_backend    TEXT    NOT NULL,
+    device_serial TEXT    NOT NULL,
+    log_archive   TEXT
+);
+
+CREATE INDEX sessions_game_id_start_time ON sessions (game_id, start_time);
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE known_devices (
+    serial TEXT NOT NULL PRIMARY KEY,
+    manufacturer TEXT NOT NULL,
+    product_name TEXT NOT NULL,
+    network_address TEXT NOT NULL
+);
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE launch_requests (
+    token TEXT NOT NULL PRIMARY KEY,
+    launch_id TEXT NOT NULL,
+    game_id TEXT NOT NULL,
+    outcome TEXT,
+    error TEXT,
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX launch_requests_launch_id ON launch_requests (launch_id);
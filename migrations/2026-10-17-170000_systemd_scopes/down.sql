@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE sessions DROP COLUMN peak_memory_bytes;
+ALTER TABLE sessions DROP COLUMN cpu_time_ms;
+ALTER TABLE games DROP COLUMN use_systemd_scope;
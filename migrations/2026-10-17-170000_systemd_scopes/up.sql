@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE games ADD COLUMN use_systemd_scope INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE sessions ADD COLUMN cpu_time_ms BIGINT;
+ALTER TABLE sessions ADD COLUMN peak_memory_bytes BIGINT;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE sessions DROP COLUMN diagnostics_bundle;
+ALTER TABLE sessions DROP COLUMN crash_kind;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE sessions ADD COLUMN crash_kind TEXT;
+ALTER TABLE sessions ADD COLUMN diagnostics_bundle TEXT;
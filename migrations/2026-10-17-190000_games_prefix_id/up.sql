@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE games ADD COLUMN prefix_id TEXT;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE installed_verbs;
+ALTER TABLE games DROP COLUMN winetricks_verbs;
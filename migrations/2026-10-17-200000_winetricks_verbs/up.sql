@@ -0,0 +1,10 @@
+-- Your SQL goes here
+ALTER TABLE games ADD COLUMN winetricks_verbs TEXT NOT NULL DEFAULT '';
+
+CREATE TABLE installed_verbs
+(
+    prefix_id    TEXT   NOT NULL,
+    verb         TEXT   NOT NULL,
+    installed_at BIGINT NOT NULL,
+    PRIMARY KEY (prefix_id, verb)
+);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE games ADD COLUMN launch_options TEXT NOT NULL DEFAULT '';
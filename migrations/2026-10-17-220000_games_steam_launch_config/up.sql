@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE games ADD COLUMN steam_launch_config TEXT;
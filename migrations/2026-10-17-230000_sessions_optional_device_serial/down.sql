@@ -0,0 +1,27 @@
+-- This file should undo anything in `up.sql`
+CREATE TABLE sessions_old
+(
+    id                 INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    game_id            TEXT    NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+    start_time         BIGINT  NOT NULL,
+    end_time           BIGINT  NOT NULL,
+    exit_code          INTEGER,
+    exit_signal        INTEGER,
+    vr_backend         TEXT    NOT NULL,
+    device_serial      TEXT    NOT NULL,
+    log_archive        TEXT,
+    cpu_time_ms        BIGINT,
+    peak_memory_bytes  BIGINT,
+    crash_kind         TEXT,
+    diagnostics_bundle TEXT
+);
+
+INSERT INTO sessions_old
+SELECT id, game_id, start_time, end_time, exit_code, exit_signal, vr_backend, COALESCE(device_serial, ''),
+       log_archive, cpu_time_ms, peak_memory_bytes, crash_kind, diagnostics_bundle
+FROM sessions;
+
+DROP TABLE sessions;
+ALTER TABLE sessions_old RENAME TO sessions;
+
+CREATE INDEX sessions_game_id_start_time ON sessions (game_id, start_time);
@@ -4,6 +4,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use num_enum::TryFromPrimitive;
 use udev::Device;
 use crate::adb::wifi_info::WifiInfo;
+use crate::adb::wireless;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
@@ -17,6 +18,8 @@ pub struct AdbVrDevice {
     pub dev_path: String,
     pub ip_address: Option<String>,
     pub wifi_info: Option<WifiInfo>,
+    /// The `ip:port` of the wireless debugging connection, for devices added over the network
+    pub network_address: Option<String>,
 }
 
 impl AdbVrDevice {
@@ -51,31 +54,7 @@ impl AdbVrDevice {
             Some(device_name),
         ) = (vendor_id, product_id, device_serial, device_manufacturer, device_name)
         {
-            let ip_output = String::from_utf8(Command::new("adb")
-                .args(&[
-                    "-s", &device_serial,
-                    "shell", "ip", "addr", "show", "wlan0"
-                ])
-                .output()?.stdout)?;
-            let ip_address = ip_output
-                .lines()
-                .into_iter()
-                .find(|line| line.contains("inet ") && line.contains("scope global"))
-                .and_then(|line| line.trim().split(' ').nth(1))
-                .and_then(|line| line.split('/').next());
-
-            let wifi_info_output = String::from_utf8(Command::new("adb")
-                .args(&[
-                    "-s", device_serial,
-                    "shell", "dumpsys", "wifi"
-                ])
-                .output()?.stdout)?;
-
-            let wifi_info_output = wifi_info_output
-                .lines()
-                .into_iter()
-                .find(|line| line.starts_with("mWifiInfo "))
-                .unwrap();
+            let (ip_address, wifi_info) = Self::query_network_info(device_serial)?;
 
             Ok(AdbVrDevice {
                 is_usb_connected: Arc::new(AtomicBool::new(true)),
@@ -85,13 +64,79 @@ impl AdbVrDevice {
                 product_name: device_name.into(),
                 usb_serial: device_serial.into(),
                 dev_path: dev_path.into(),
-                ip_address: ip_address.map(|ip| ip.into()),
-                wifi_info: WifiInfo::parse_from(&wifi_info_output),
+                ip_address,
+                wifi_info,
+                network_address: None,
             })
         } else {
             Err(anyhow::anyhow!("Unable to parse this device as a valid VR device").into())
         }
     }
+
+    /// Builds a device from an existing wireless ADB connection, without it ever being seen over USB
+    pub fn try_from_network(network_address: &str) -> anyhow::Result<Self> {
+        wireless::connect(network_address)?;
+
+        let get_prop = |name: &str| -> anyhow::Result<String> {
+            let output = Command::new("adb")
+                .args(["-s", network_address, "shell", "getprop", name])
+                .output()?;
+            Ok(String::from_utf8(output.stdout)?.trim().to_string())
+        };
+
+        let serial = get_prop("ro.serialno")?;
+        let manufacturer = get_prop("ro.product.manufacturer")?;
+        let product_name = get_prop("ro.product.model")?;
+        if serial.is_empty() {
+            return Err(anyhow::anyhow!("Could not read the serial number of {}", network_address));
+        }
+
+        let dev_type = VrDeviceType::from_manufacturer(&manufacturer)
+            .ok_or_else(|| anyhow::anyhow!("{} {} is not a supported VR device", manufacturer, product_name))?;
+        let (ip_address, wifi_info) = Self::query_network_info(network_address)?;
+
+        Ok(AdbVrDevice {
+            is_usb_connected: Arc::new(AtomicBool::new(false)),
+            dev_type,
+            product_id: 0,
+            manufacturer,
+            product_name,
+            usb_serial: serial,
+            dev_path: String::new(),
+            ip_address: ip_address.or_else(|| network_address.split(':').next().map(|ip| ip.to_string())),
+            wifi_info,
+            network_address: Some(network_address.to_string()),
+        })
+    }
+
+    fn query_network_info(conn_id: &str) -> anyhow::Result<(Option<String>, Option<WifiInfo>)> {
+        let ip_output = String::from_utf8(Command::new("adb")
+            .args([
+                "-s", conn_id,
+                "shell", "ip", "addr", "show", "wlan0"
+            ])
+            .output()?.stdout)?;
+        let ip_address = ip_output
+            .lines()
+            .find(|line| line.contains("inet ") && line.contains("scope global"))
+            .and_then(|line| line.trim().split(' ').nth(1))
+            .and_then(|line| line.split('/').next())
+            .map(|ip| ip.to_string());
+
+        let wifi_info_output = String::from_utf8(Command::new("adb")
+            .args([
+                "-s", conn_id,
+                "shell", "dumpsys", "wifi"
+            ])
+            .output()?.stdout)?;
+
+        let wifi_info = wifi_info_output
+            .lines()
+            .find(|line| line.starts_with("mWifiInfo "))
+            .and_then(WifiInfo::parse_from);
+
+        Ok((ip_address, wifi_info))
+    }
 }
 
 impl AdbVrDevice {
@@ -150,9 +195,13 @@ impl AdbVrDevice {
     }
 
     fn get_conn_id(&self) -> anyhow::Result<String> {
-        match (self.is_usb_connected.load(Ordering::SeqCst), self.ip_address.as_ref()) {
-            (true, _) => Ok(self.usb_serial.clone()),
-            (false, Some(ip)) => {
+        match (self.is_usb_connected.load(Ordering::SeqCst), self.network_address.as_ref(), self.ip_address.as_ref()) {
+            (true, _, _) => Ok(self.usb_serial.clone()),
+            (false, Some(network_address), _) => {
+                wireless::connect(network_address)?;
+                Ok(network_address.clone())
+            }
+            (false, None, Some(ip)) => {
                 if let Ok(_) = self.try_connect_tcpip(5555) {
                     Ok(format!("{}:{}", ip, 5555))
                 } else {
@@ -173,4 +222,19 @@ pub enum VrDeviceType {
     Microsoft = 0x045e,
     Oculus = 0x2833,
     Valve = 0x28de,
+}
+
+impl VrDeviceType {
+    /// Network devices have no USB vendor id, so the `ro.product.manufacturer` property is used instead
+    pub fn from_manufacturer(manufacturer: &str) -> Option<Self> {
+        match manufacturer.to_lowercase().as_str() {
+            "oculus" | "meta" => Some(VrDeviceType::Oculus),
+            "htc" => Some(VrDeviceType::HTC),
+            "sony" => Some(VrDeviceType::Sony),
+            "lenovo" => Some(VrDeviceType::Lenovo),
+            "microsoft" => Some(VrDeviceType::Microsoft),
+            "valve" => Some(VrDeviceType::Valve),
+            _ => None,
+        }
+    }
 }
\ No newline at end of file
@@ -1,29 +1,62 @@
+use std::collections::HashMap;
 use std::process::Command;
 use crate::adb::adb_device::AdbVrDevice;
+use crate::adb::wireless;
+use crate::database::DbPool;
+use crate::models::KnownDevice;
+use crate::schema::known_devices;
 use crate::TokioMutex;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use serde::Serialize;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use tokio::sync::broadcast;
 use tokio::sync::broadcast::Sender;
 use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration};
+use ts_rs::TS;
 use udev::{Enumerator, MonitorBuilder};
 
 pub struct DeviceManager {
-    current_device: Arc<TokioMutex<Option<AdbVrDevice>>>,
+    connected_devices: Arc<TokioMutex<ConnectedDevices>>,
     force_update_tx: Sender<()>,
+    db_pool: DbPool,
     _monitor_thread: JoinHandle<()>,
 }
 
+/// Every VR device seen since startup, keyed by USB serial. Unplugged devices are kept,
+/// because they might still be reachable over the network.
+#[derive(Default)]
+struct ConnectedDevices {
+    devices: HashMap<String, AdbVrDevice>,
+    selected_serial: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct VrDeviceInfo {
+    pub serial: String,
+    pub manufacturer: String,
+    pub product_name: String,
+    pub ip_address: Option<String>,
+    pub network_address: Option<String>,
+    pub is_usb_connected: bool,
+    pub is_selected: bool,
+}
+
 impl DeviceManager {
-    pub fn new(stop_tx: Sender<()>) -> anyhow::Result<Self> {
-        let current_device = Arc::new(TokioMutex::new(Self::find_connected_device()?));
+    pub fn new(stop_tx: Sender<()>, db_pool: DbPool) -> anyhow::Result<Self> {
+        let connected_devices = Arc::new(TokioMutex::new(Self::find_connected_devices()?));
         let mut stop_rx = stop_tx.subscribe();
         let (force_update_tx, _) = broadcast::channel(1);
 
+        Self::reconnect_known_devices(connected_devices.clone(), force_update_tx.clone(), db_pool.clone());
+
         Ok(Self {
-            current_device: current_device.clone(),
+            connected_devices: connected_devices.clone(),
             force_update_tx: force_update_tx.clone(),
+            db_pool,
             _monitor_thread: tokio::task::spawn(async move {
                 let socket = match MonitorBuilder::new()
                     .and_then(|builder| builder.match_subsystem_devtype("usb", "usb_device"))
@@ -47,32 +80,37 @@ impl DeviceManager {
                                     let action = event.action().and_then(|str| str.to_str());
                                     let dev_path = event.devpath();
     
-                                    let mut current_device = current_device.lock().await;
+                                    let mut connected_devices = connected_devices.lock().await;
     
                                     match action {
                                         Some("bind") => {
-                                            if let Ok(device) = AdbVrDevice::try_from(&event.device()) {
+                                            if let Ok(mut device) = AdbVrDevice::try_from(&event.device()) {
                                                 println!("  VR Device Connected: {:?}", device);
-                                                current_device.replace(device);
-                                                _ = force_update_tx.send(());
+                                                let serial = device.usb_serial.clone();
+                                                if let Some(known_device) = connected_devices.devices.get(&serial) {
+                                                    device.network_address = known_device.network_address.clone();
+                                                }
+                                                connected_devices.devices.insert(serial.clone(), device);
+
+                                                // A newly attached headset never takes over an existing selection
+                                                let selected_serial = connected_devices.selected_serial.get_or_insert(serial.clone());
+                                                if *selected_serial == serial {
+                                                    _ = force_update_tx.send(());
+                                                }
                                             }
                                         }
                                         Some("unbind") => {
-                                            let device_path = current_device
-                                                .as_ref()
-                                                .map(|d| d.dev_path.as_str());
+                                            let selected_serial = connected_devices.selected_serial.clone();
+                                            let disconnected_device = connected_devices.devices
+                                                .values()
+                                                .find(|d| d.dev_path.as_str() == dev_path);
     
-                                            match device_path {
-                                                Some(disconn_dev_path) if disconn_dev_path == dev_path => {
-                                                    if let Some(device) = current_device.as_ref() {
-                                                        if device.dev_path == disconn_dev_path {
-                                                            println!("  VR Device Disconnected: {:?}", device);
-                                                            device.is_usb_connected.store(false, Ordering::SeqCst);
-                                                            _ = force_update_tx.send(());
-                                                        }
-                                                    }
+                                            if let Some(device) = disconnected_device {
+                                                println!("  VR Device Disconnected: {:?}", device);
+                                                device.is_usb_connected.store(false, Ordering::SeqCst);
+                                                if selected_serial.as_ref() == Some(&device.usb_serial) {
+                                                    _ = force_update_tx.send(());
                                                 }
-                                                _ => {}
                                             }
                                         }
                                         _ => {}
@@ -119,9 +157,161 @@ impl DeviceManager {
         Ok(event)
     }
 
-    pub async fn get_current_device_async(&self) -> anyhow::Result<Option<AdbVrDevice>> {
-        let current_device = self.current_device.lock().await;
-        Ok(current_device.clone())
+    pub async fn get_selected_device_async(&self) -> anyhow::Result<Option<AdbVrDevice>> {
+        let connected_devices = self.connected_devices.lock().await;
+        Ok(connected_devices.selected_serial.as_ref()
+            .and_then(|serial| connected_devices.devices.get(serial))
+            .cloned())
+    }
+
+    pub async fn list_devices_async(&self) -> Vec<VrDeviceInfo> {
+        let connected_devices = self.connected_devices.lock().await;
+        let mut devices = connected_devices.devices.values()
+            .map(|device| VrDeviceInfo {
+                serial: device.usb_serial.clone(),
+                manufacturer: device.manufacturer.clone(),
+                product_name: device.product_name.clone(),
+                ip_address: device.ip_address.clone(),
+                network_address: device.network_address.clone(),
+                is_usb_connected: device.is_usb_connected.load(Ordering::SeqCst),
+                is_selected: connected_devices.selected_serial.as_ref() == Some(&device.usb_serial),
+            })
+            .collect::<Vec<_>>();
+        devices.sort_by(|a, b| a.serial.cmp(&b.serial));
+
+        devices
+    }
+
+    pub async fn select_device_async(&self, serial: &str) -> anyhow::Result<()> {
+        let mut connected_devices = self.connected_devices.lock().await;
+        if !connected_devices.devices.contains_key(serial) {
+            return Err(anyhow::anyhow!("Unknown VR device: {}", serial));
+        }
+
+        if connected_devices.selected_serial.as_deref() != Some(serial) {
+            println!("  VR Device Selected: {}", serial);
+            connected_devices.selected_serial = Some(serial.to_string());
+            _ = self.force_update_tx.send(());
+        }
+
+        Ok(())
+    }
+
+    /// Connects to a headset over wireless ADB and remembers it for the next startup
+    pub async fn connect_network_device_async(&self, network_address: &str) -> anyhow::Result<VrDeviceInfo> {
+        let address = network_address.to_string();
+        let device = tokio::task::spawn_blocking(move || AdbVrDevice::try_from_network(&address)).await??;
+        let serial = device.usb_serial.clone();
+
+        let known_device = KnownDevice {
+            serial: serial.clone(),
+            manufacturer: device.manufacturer.clone(),
+            product_name: device.product_name.clone(),
+            network_address: network_address.to_string(),
+        };
+        let connection = &mut self.db_pool.get()?;
+        diesel::replace_into(known_devices::table)
+            .values(&known_device)
+            .execute(connection)?;
+
+        Self::add_network_device(&mut *self.connected_devices.lock().await, device, &self.force_update_tx);
+
+        self.list_devices_async().await
+            .into_iter()
+            .find(|d| d.serial == serial)
+            .ok_or_else(|| anyhow::anyhow!("The device {} has disappeared", serial))
+    }
+
+    /// Drops the wireless connection of a headset, and removes it entirely unless it is plugged in
+    pub async fn forget_device_async(&self, serial: &str) -> anyhow::Result<bool> {
+        let connection = &mut self.db_pool.get()?;
+        let deleted = diesel::delete(known_devices::table.find(serial))
+            .execute(connection)?;
+
+        let mut connected_devices = self.connected_devices.lock().await;
+        let Some(device) = connected_devices.devices.get_mut(serial) else {
+            return Ok(deleted > 0);
+        };
+
+        if let Some(network_address) = device.network_address.take() {
+            wireless::disconnect(&network_address)?;
+        }
+
+        if !device.is_usb_connected.load(Ordering::SeqCst) {
+            connected_devices.devices.remove(serial);
+            if connected_devices.selected_serial.as_deref() == Some(serial) {
+                connected_devices.selected_serial = connected_devices.devices.keys().min().cloned();
+                _ = self.force_update_tx.send(());
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn add_network_device(connected_devices: &mut ConnectedDevices, device: AdbVrDevice, force_update_tx: &Sender<()>) {
+        let serial = device.usb_serial.clone();
+        match connected_devices.devices.get_mut(&serial) {
+            // Keep the USB connection of a headset that is also plugged in
+            Some(existing) => existing.network_address = device.network_address,
+            None => {
+                println!("  VR Device Connected over the network: {:?}", device);
+                connected_devices.devices.insert(serial.clone(), device);
+            }
+        }
+
+        if connected_devices.selected_serial.get_or_insert(serial.clone()) == &serial {
+            _ = force_update_tx.send(());
+        }
+    }
+
+    fn reconnect_known_devices(connected_devices: Arc<TokioMutex<ConnectedDevices>>, force_update_tx: Sender<()>, db_pool: DbPool) {
+        tokio::task::spawn(async move {
+            let known_devices = match db_pool.get()
+                .map_err(anyhow::Error::from)
+                .and_then(|mut connection| Ok(known_devices::table
+                    .select(KnownDevice::as_select())
+                    .load(&mut connection)?)) {
+                Ok(known_devices) => known_devices,
+                Err(e) => {
+                    eprintln!("Failed to load the known VR devices: {}", e);
+                    return;
+                }
+            };
+
+            for known_device in known_devices {
+                let (serial, stored_address) = (known_device.serial.clone(), known_device.network_address.clone());
+                let result = tokio::task::spawn_blocking(move || {
+                    // Falls back to the stored address if the headset does not advertise itself (yet)
+                    let address = wireless::find_connect_address(&serial).ok().flatten().unwrap_or(stored_address);
+                    AdbVrDevice::try_from_network(&address).map(|device| (device, address))
+                }).await;
+
+                match result {
+                    Ok(Ok((device, address))) => {
+                        if address != known_device.network_address {
+                            println!("{} has moved to {}", known_device.product_name, address);
+                            if let Err(e) = Self::update_known_address(&db_pool, &known_device.serial, &address) {
+                                eprintln!("Failed to store the new address of {}: {}", known_device.product_name, e);
+                            }
+                        }
+
+                        let mut connected_devices = connected_devices.lock().await;
+                        Self::add_network_device(&mut connected_devices, device, &force_update_tx);
+                    }
+                    Ok(Err(e)) => eprintln!("Failed to reconnect to {} at {}: {}", known_device.product_name, known_device.network_address, e),
+                    Err(e) => eprintln!("Failed to reconnect to {}: {}", known_device.product_name, e),
+                }
+            }
+        });
+    }
+
+    fn update_known_address(db_pool: &DbPool, serial: &str, network_address: &str) -> anyhow::Result<()> {
+        let connection = &mut db_pool.get()?;
+        diesel::update(known_devices::table.find(serial))
+            .set(known_devices::network_address.eq(network_address))
+            .execute(connection)?;
+
+        Ok(())
     }
 
     pub fn disconnect_tcpip(&self) -> anyhow::Result<()> {
@@ -132,16 +322,18 @@ impl DeviceManager {
         Ok(())
     }
 
-    fn find_connected_device() -> anyhow::Result<Option<AdbVrDevice>> {
+    fn find_connected_devices() -> anyhow::Result<ConnectedDevices> {
         let mut enumerator = Enumerator::new()?;
         enumerator.match_subsystem("usb")?;
 
+        let mut connected_devices = ConnectedDevices::default();
         for device in enumerator.scan_devices()? {
             if let Ok(vr_device) = AdbVrDevice::try_from(&device) {
-                return Ok(Some(vr_device));
+                connected_devices.devices.insert(vr_device.usb_serial.clone(), vr_device);
             }
         }
+        connected_devices.selected_serial = connected_devices.devices.keys().min().cloned();
 
-        Ok(None)
+        Ok(connected_devices)
     }
 }
\ No newline at end of file
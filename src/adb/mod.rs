@@ -1,3 +1,4 @@
 pub mod device_manager;
 pub mod adb_device;
-pub mod wifi_info;
\ No newline at end of file
+pub mod wifi_info;
+pub mod wireless;
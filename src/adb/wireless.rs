@@ -0,0 +1,144 @@
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+const CONNECT_SERVICE_TYPE: &str = "_adb-tls-connect._tcp";
+const PAIRING_SERVICE_TYPE: &str = "_adb-tls-pairing._tcp";
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct AdbMdnsService {
+    pub name: String,
+    pub address: String,
+    pub is_pairing: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairRequest {
+    /// The `ip:port` shown in the headset's "Pair device with pairing code" dialog
+    pub address: String,
+    pub pairing_code: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectRequest {
+    pub address: String,
+}
+
+/// Pairs with a headset using the six-digit code from its wireless debugging settings
+pub fn pair(address: &str, pairing_code: &str) -> anyhow::Result<()> {
+    let output = Command::new("adb")
+        .args(["pair", address, pairing_code])
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    match stdout.contains("Successfully paired") {
+        true => Ok(()),
+        false => Err(anyhow::anyhow!("Failed to pair with {}: {}{}", address, stdout.trim(),
+            String::from_utf8_lossy(&output.stderr).trim())),
+    }
+}
+
+pub fn connect(address: &str) -> anyhow::Result<()> {
+    let output = Command::new("adb")
+        .args(["connect", address])
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    match stdout.contains("connected to") && !stdout.contains("failed") {
+        true => Ok(()),
+        false => Err(anyhow::anyhow!("Failed to connect to {}: {}", address, stdout.trim())),
+    }
+}
+
+pub fn disconnect(address: &str) -> anyhow::Result<()> {
+    Command::new("adb")
+        .args(["disconnect", address])
+        .output()?;
+
+    Ok(())
+}
+
+/// Lists the headsets that advertise wireless debugging on the local network
+pub fn discover_services() -> anyhow::Result<Vec<AdbMdnsService>> {
+    let output = Command::new("adb")
+        .args(["mdns", "services"])
+        .output()?;
+
+    Ok(parse_mdns_services(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The current wireless debugging address of a headset. Its port changes whenever the headset
+/// reboots or reconnects to Wi-Fi, so a stored address goes stale quickly.
+pub fn find_connect_address(serial: &str) -> anyhow::Result<Option<String>> {
+    Ok(discover_services()?
+        .into_iter()
+        .find(|service| !service.is_pairing && is_service_of(&service.name, serial))
+        .map(|service| service.address))
+}
+
+/// Headsets advertise themselves as `adb-<serial>-<suffix>`
+fn is_service_of(name: &str, serial: &str) -> bool {
+    name.strip_prefix("adb-")
+        .and_then(|rest| rest.strip_prefix(serial))
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+}
+
+fn parse_mdns_services(output: &str) -> Vec<AdbMdnsService> {
+    output.lines()
+        .filter_map(|line| {
+            let parts = line.split_whitespace().collect::<Vec<_>>();
+            let [name, service_type, address] = parts[..] else {
+                return None;
+            };
+
+            let is_pairing = match service_type.trim_end_matches('.') {
+                CONNECT_SERVICE_TYPE => false,
+                PAIRING_SERVICE_TYPE => true,
+                _ => return None,
+            };
+
+            Some(AdbMdnsService {
+                name: name.to_string(),
+                address: address.to_string(),
+                is_pairing,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_services_by_serial() {
+        assert!(is_service_of("adb-1WMHH000000000-AbCdEf", "1WMHH000000000"));
+        assert!(!is_service_of("adb-1WMHH000000000-AbCdEf", "1WMHH0000"));
+        assert!(!is_service_of("1WMHH000000000-AbCdEf", "1WMHH000000000"));
+    }
+
+    #[test]
+    fn parses_mdns_services() {
+        let output = "List of discovered mdns services\n\
+            adb-1WMHH000000000-AbCdEf\t_adb-tls-connect._tcp\t192.168.1.42:37215\n\
+            adb-1WMHH000000000-AbCdEf\t_adb-tls-pairing._tcp.\t192.168.1.42:40123\n\
+            some-printer\t_ipp._tcp\t192.168.1.7:631\n";
+
+        assert_eq!(parse_mdns_services(output), vec![
+            AdbMdnsService {
+                name: "adb-1WMHH000000000-AbCdEf".into(),
+                address: "192.168.1.42:37215".into(),
+                is_pairing: false,
+            },
+            AdbMdnsService {
+                name: "adb-1WMHH000000000-AbCdEf".into(),
+                address: "192.168.1.42:40123".into(),
+                is_pairing: true,
+            },
+        ]);
+    }
+}
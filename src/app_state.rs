@@ -1,55 +1,252 @@
 use crate::adb::device_manager::DeviceManager;
 use crate::audio_api::PipeWireManager;
-use crate::backends::envision::envision_backend::EnvisionBackend;
-use crate::backends::wivrn::wivrn_backend::WiVRnBackend;
+use crate::backends::registry::BackendRegistry;
+use crate::backends::supervisor::BackendHealth;
 use crate::backends::{BackendType, VRBackend};
 use crate::battery_monitor::BatteryMonitor;
 use crate::command_parser::parse_linux_command;
 use crate::logging::log_session::LogSession;
-use crate::models::Game;
+use crate::database::DbPool;
+use crate::diagnostics::{self, CrashInfo, DiagnosticsReport, SystemReport};
+use crate::idempotency::{self, LaunchOutcome};
+use crate::launch::{ActiveLaunch, LaunchProgress, LaunchState, LaunchTask, PreparedLaunch};
+use crate::events::{ServerEvent, StateSnapshot};
+use crate::models::{Game, NewSession};
+use crate::schema::games;
+use crate::schema::sessions::dsl::sessions;
 use crate::overlay::WlxOverlayManager;
 use crate::steam::launch_modifiers::env_vars::EnvironmentVariablesModifier;
 use crate::steam::launch_modifiers::steam::SteamLaunchModifier;
 use crate::steam::launch_modifiers::LaunchModifier;
+use crate::steam::launch_options::LaunchOptions;
 use crate::steam::launcher::CompatLauncher;
+use crate::steam::prefix;
+use crate::steam::process_tree::{GameProcessTree, DEFAULT_GRACE_PERIOD};
+use crate::steam::library_import::SteamImportJob;
 use crate::steam::steam_interface::{ProtonLaunchInfo, SteamApp, SteamAppPlatform, SteamInterface};
 use crate::GameSession;
-use anyhow::ensure;
-use nix::libc::pid_t;
-use std::collections::{HashMap, HashSet};
+use diesel::{QueryDsl, RunQueryDsl, SelectableHelper};
+use std::collections::{HashSet, VecDeque};
 use std::env;
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
-use sysinfo::System;
 use tokio::sync::{broadcast, Mutex};
-use tokio::time::Instant;
+use uuid::Uuid;
 
 pub struct AppState {
     pub audio_api: PipeWireManager,
     pub steam_api: SteamInterface,
     pub launcher: Arc<CompatLauncher>,
     pub active_game_session: Option<GameSession>,
-    pub sock_tx: broadcast::Sender<String>,
+    pub sock_tx: broadcast::Sender<ServerEvent>,
     pub device_manager: Arc<Mutex<DeviceManager>>,
-    pub active_backend: Option<Box<dyn VRBackend + Send>>,
+    pub active_backend: Option<Box<dyn VRBackend + Send + Sync>>,
+    /// The `vr_backend` and `vr_backend_args` the active backend was created with
+    pub active_backend_config: Option<(String, String)>,
     pub backend_type: BackendType,
+    pub backend_registry: BackendRegistry,
     pub battery_monitor: BatteryMonitor,
     pub overlay_manager: WlxOverlayManager,
     pub log_session: Option<LogSession>,
-    pub launch_requests: HashSet<String>,
+    pub active_launch: Option<ActiveLaunch>,
+    /// Launches that wait for the current session to end, see [`AppState::queue_launch`]
+    pub launch_queue: VecDeque<LaunchProgress>,
+    /// Only set once the backend of the current session has died, see `backends::supervisor`
+    pub backend_health: Option<BackendHealth>,
+    /// Prefixes that are being reset, backed up or restored, no game using them can be launched meanwhile
+    pub busy_prefixes: HashSet<String>,
     pub socket_stop_tx: broadcast::Sender<()>,
+    pub steam_import: SteamImportJob,
+    pub db_pool: DbPool,
 }
 
 pub type AppStateWrapper = Arc<Mutex<AppState>>;
 
 impl AppState {
-    pub async fn launch_game_async(&mut self, game: Game) -> anyhow::Result<()> {
+    /// Registers a new launch and runs its steps in the background, see [`LaunchTask`]
+    pub fn start_launch(&mut self, app_state: AppStateWrapper, mut game: Game) -> anyhow::Result<String> {
+        self.ensure_prefix_available(&mut game)?;
         if let Some(_) = &self.active_game_session {
             return Err(anyhow::anyhow!("Another active game session is already running"));
         }
+        if let Some(launch) = &self.active_launch {
+            return Err(anyhow::anyhow!("Another game is already being launched ({})", launch.progress.game_id));
+        }
+
+        Ok(self.begin_launch(app_state, Uuid::new_v4().to_string(), game))
+    }
+
+    /// Assigns the prefix id of `game` if it has none yet, since games may share a prefix
+    fn ensure_prefix_available(&self, game: &mut Game) -> anyhow::Result<()> {
+        let connection = &mut self.db_pool.get()?;
+        let prefix_id = prefix::ensure_prefix_id(connection, game)?;
+        if self.busy_prefixes.contains(&prefix_id) {
+            return Err(anyhow::anyhow!("The prefix of {} is being changed, try again once that is done", game.title));
+        }
+        game.prefix_id = Some(prefix_id);
+
+        Ok(())
+    }
+
+    fn begin_launch(&mut self, app_state: AppStateWrapper, launch_id: String, game: Game) -> String {
+        let launch = ActiveLaunch::new(LaunchProgress {
+            launch_id: launch_id.clone(),
+            game_id: game.id.clone(),
+            state: LaunchState::Preparing,
+            error: None,
+        });
+        let cancel_requested = launch.cancel_requested.clone();
+        self.broadcast_launch_progress(&launch.progress);
+        self.active_launch.replace(launch);
+
+        tokio::spawn(LaunchTask::new(app_state, launch_id.clone(), game, cancel_requested).run());
+
+        launch_id
+    }
+
+    /// Stops the running game so `game` can be launched in its place, see [`AppState::begin_stopping_game`].
+    /// The backend and the overlay are kept alive when the new game uses the same backend with the same arguments.
+    /// The launch goes to the front of the queue right away, so nothing else can start once the old game
+    /// has exited. Returns the launch id, and the processes the caller has to terminate.
+    pub fn begin_switch(&mut self, app_state: AppStateWrapper, mut game: Game) -> anyhow::Result<(String, Option<GameProcessTree>)> {
+        if let Some(launch) = &self.active_launch
+            && launch.progress.state != LaunchState::Running {
+            return Err(anyhow::anyhow!("Another game is already being launched ({})", launch.progress.game_id));
+        }
+        if self.active_game_session.is_none() {
+            return Ok((self.start_launch(app_state, game)?, None));
+        }
+        self.ensure_prefix_available(&mut game)?;
+
+        let keep_backend = self.active_backend_config.as_ref() == Some(&(game.vr_backend.clone(), game.vr_backend_args.clone()));
+        println!("Switching to game {} (keep backend: {})", game.id, keep_backend);
+
+        let progress = LaunchProgress {
+            launch_id: Uuid::new_v4().to_string(),
+            game_id: game.id,
+            state: LaunchState::Queued,
+            error: None,
+        };
+        self.broadcast_launch_progress(&progress);
+        self.launch_queue.push_front(progress.clone());
+
+        Ok((progress.launch_id, self.begin_stopping_game(keep_backend)))
+    }
+
+    /// Launches `game` once the current session (or launch) has ended, or right away if nothing is running
+    pub fn queue_launch(&mut self, app_state: AppStateWrapper, mut game: Game) -> anyhow::Result<String> {
+        self.ensure_prefix_available(&mut game)?;
+        if self.active_game_session.is_none() && self.active_launch.is_none() {
+            return self.start_launch(app_state, game);
+        }
+
+        let progress = LaunchProgress {
+            launch_id: Uuid::new_v4().to_string(),
+            game_id: game.id,
+            state: LaunchState::Queued,
+            error: None,
+        };
+        self.broadcast_launch_progress(&progress);
+        self.launch_queue.push_back(progress.clone());
+
+        Ok(progress.launch_id)
+    }
+
+    /// Starts the next queued launch, unless a game is still running or being launched
+    pub fn start_next_queued_launch(&mut self, app_state: AppStateWrapper) {
+        if self.active_game_session.is_some() || self.active_launch.is_some() {
+            return;
+        }
+        let Some(mut next) = self.launch_queue.pop_front() else {
+            self.stop_idle_backend();
+            return;
+        };
+
+        let game = self.db_pool.get()
+            .map_err(anyhow::Error::from)
+            .and_then(|mut connection| Ok(games::table.find(&next.game_id).select(Game::as_select()).first(&mut connection)?));
+        match game {
+            Ok(game) => _ = self.begin_launch(app_state, next.launch_id, game),
+            Err(error) => {
+                // The failure is broadcast as well, which moves the queue along
+                next.state = LaunchState::Failed;
+                next.error = Some(format!("Could not load the queued game: {}", error));
+                self.broadcast_launch_progress(&next);
+                self.record_launch_outcome(&next);
+            }
+        }
+    }
+
+    /// Stops a backend that was kept alive for a switch whose launch has then failed or was cancelled
+    fn stop_idle_backend(&mut self) {
+        let Some(mut backend) = self.active_backend.take() else {
+            return;
+        };
+
+        println!("Stopping the backend, no game is going to use it");
+        self.active_backend_config = None;
+        if let Err(error) = backend.stop().and_then(|_| self.overlay_manager.stop()) {
+            eprintln!("Failed to stop the idle backend: {:?}", error);
+        }
+    }
+
+    /// Removes a launch from the queue before it has started, returns false if it is not queued
+    pub fn cancel_queued_launch(&mut self, launch_id: &str) -> bool {
+        let Some(index) = self.launch_queue.iter().position(|l| l.launch_id == launch_id) else {
+            return false;
+        };
+
+        let mut launch = self.launch_queue.remove(index).unwrap();
+        launch.state = LaunchState::Cancelled;
+        self.broadcast_launch_progress(&launch);
+        self.record_launch_outcome(&launch);
+
+        true
+    }
 
+    /// Moves the active launch to `state`, unless it has already been replaced by another launch
+    pub fn set_launch_state(&mut self, launch_id: &str, state: LaunchState, error: Option<String>) {
+        if let Some(launch) = self.active_launch.as_mut()
+            && launch.progress.launch_id == launch_id {
+            launch.progress.state = state;
+            launch.progress.error = error;
+
+            let progress = launch.progress.clone();
+            self.broadcast_launch_progress(&progress);
+            self.record_launch_outcome(&progress);
+        }
+    }
+
+    /// Remembers how a launch ended, for requests that are retried with the same idempotency token
+    fn record_launch_outcome(&self, progress: &LaunchProgress) {
+        let outcome = match progress.state {
+            LaunchState::Running => LaunchOutcome::Started,
+            LaunchState::Failed => LaunchOutcome::Failed,
+            LaunchState::Cancelled => LaunchOutcome::Cancelled,
+            _ => return,
+        };
+
+        let result = self.db_pool.get()
+            .map_err(anyhow::Error::from)
+            .and_then(|mut connection| Ok(idempotency::record_outcome(&mut connection, &progress.launch_id, outcome, progress.error.as_deref())?));
+        if let Err(error) = result {
+            eprintln!("Failed to record the outcome of launch {}: {:?}", progress.launch_id, error);
+        }
+    }
+
+    fn broadcast_launch_progress(&self, progress: &LaunchProgress) {
+        println!("Launch {} is now {:?}", progress.launch_id, progress.state);
+        _ = self.sock_tx.send(ServerEvent::LaunchProgress(progress.clone()));
+    }
+
+    /// Resolves what to run and creates the backend, the first step of a launch
+    pub fn prepare_launch(&mut self, game: &Game) -> anyhow::Result<PreparedLaunch> {
+        let connection = &mut self.db_pool.get()?;
+        let prefix_id = prefix::ensure_prefix_id(connection, game)?;
         let mut modifiers: Vec<Box<dyn LaunchModifier>> = vec![
-            Box::new(SteamLaunchModifier::new()),
+            Box::new(SteamLaunchModifier::new(prefix_id.clone())),
         ];
 
         let proton_hint = match game.proton_version {
@@ -58,10 +255,7 @@ impl AppState {
         };
         
         let steam_app = match (&game.steam_app_id, &game.command_line) {
-            (Some(steam_id), None) => self.steam_api.get_installed_apps(Some(proton_hint))?
-                .into_iter()
-                .find(|app| app.steam_id == *steam_id as u32)
-                .ok_or(anyhow::anyhow!("Could not find Steam app with id {}", steam_id))?,
+            (Some(steam_id), None) => self.steam_api.get_installed_app(*steam_id as u32, Some(proton_hint), game.steam_launch_config.as_deref())?,
             (steam_id, Some(command_line)) => {
                 let command = parse_linux_command(command_line)
                     .map_err(|err| anyhow::anyhow!("Could not parse launch command: {:?}", err))?;
@@ -97,6 +291,9 @@ impl AppState {
 
         println!("Launching game: {:#?}", steam_app);
 
+        let launch_options = LaunchOptions::parse(&game.launch_options)
+            .map_err(|err| anyhow::anyhow!("Could not parse launch options: {}", err))?;
+
         let compat_version = match &game.proton_version {
             Some(version) => Some(self.steam_api.get_proton_versions()?
                 .into_iter()
@@ -107,111 +304,63 @@ impl AppState {
             None => unreachable!(),
         };
 
-        // Create logging session
-        self.start_log_session()?;
+        // Set up backend, reusing the one that was kept alive by a switch if it matches
+        let backend_config = (game.vr_backend.clone(), game.vr_backend_args.clone());
+        let reuse_backend = self.active_backend.is_some() && self.active_backend_config.as_ref() == Some(&backend_config);
 
-        // Set up backend
-        if let Some(active_backend) = self.active_backend.as_mut() {
-            active_backend.stop()?;
-        }
-
-        let mut backend: Box<dyn VRBackend + Send> = match game.vr_backend.to_lowercase().as_str() {
-            "wivrn" => {
-                self.backend_type = BackendType::WiVRn;
-                Box::new(WiVRnBackend::new(game.vr_backend_args.clone().try_into().ok())?)
-            }
-            "envision" => {
-                self.backend_type = BackendType::Envision;
-                Box::new(EnvisionBackend::new(game.vr_backend_args.clone())?)
-            }
-            _ => return Err(anyhow::anyhow!("This VR backend is currently not supported!")),
-        };
-        backend.add_modifiers(&mut modifiers)?;
-
-        // Check if headset is currently mounted
-        {
-            let device_manager = self.device_manager.lock().await;
-            let active_device = device_manager.get_current_device_async().await?
-                .ok_or_else(|| anyhow::anyhow!("No active device found"))?;
-
-            ensure!(
-                active_device.is_hmd_mounted()?,
-                "Please mount the headset before starting any games."
-            );
-            drop(device_manager);
-        }
+        // Create logging session. A reused backend and its overlay keep logging to their channels.
+        self.start_log_session(reuse_backend)?;
 
-        // Start backend
-        let backend_log_channel = self.log_session.as_mut().unwrap()
-            .create_channel("vr_backend")?;
-        let device_manager = self.device_manager.clone();
-        let start_info = backend.start_async(backend_log_channel, device_manager).await?;
-        self.battery_monitor.set_active_device_serial(start_info.vr_device_serial.clone());
-        if let Some(device_ip) = start_info.vr_device_ip {
-            self.battery_monitor.set_active_device_ip(device_ip);
-        }
-        let mut backend_loaded = false;
-        for _ in 0..=50 {
-            if backend.is_ready().await? {
-                backend_loaded = true;
-                break;
+        let mut backend = match self.active_backend.take() {
+            Some(backend) if reuse_backend => {
+                println!("Reusing the running {} backend", game.vr_backend);
+                backend
             }
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-        }
-        
-        if !backend_loaded {
-            return Err(anyhow::anyhow!("Could not start the backend."));
-        }
+            active_backend => {
+                if let Some(mut active_backend) = active_backend {
+                    active_backend.stop()?;
+                }
 
-        // Wait for virtual audio devices to be registered
-        let start = Instant::now();
-        let (output_device, input_device) = loop {
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-
-            let output = self.audio_api.get_output_devices().iter()
-                .find(|d| backend.is_matching_audio_device(d))
-                .cloned();
-            let input = self.audio_api.get_input_devices().iter()
-                .find(|d| backend.is_matching_audio_device(d))
-                .cloned();
-
-            if start.elapsed().as_secs() > 3 && output.is_none() && input.is_none() {
-                eprintln!("Couldn't find the virtual audio devices for this backend!");
-                break (None, None);
-            } else if output.is_some() && input.is_some() {
-                break (output, input);
+                let (backend_type, backend) = self.backend_registry.create(&game.vr_backend, &game.vr_backend_args)?;
+                self.backend_type = backend_type;
+                backend
             }
         };
-
-        if let (Some(output_device), Some(input_device)) = (output_device, input_device) {
-            self.audio_api.set_default_output_device(&output_device);
-            self.audio_api.set_default_input_device(&input_device);
-            println!("Changed default output devices to '{}' / '{}'", output_device.description, input_device.description);
-        }
-
-        self.active_backend.replace(backend);
-
-        // Start the overlay
-        if start_info.was_restarted && game.use_overlay {
-            let backend_log_channel = self.log_session.as_mut().unwrap()
-                .create_channel("overlay")?;
-            self.overlay_manager.start(backend_log_channel)?;
+        if let Err(error) = backend.add_modifiers(&mut modifiers) {
+            // A reused backend is still running, and the launch has nothing to clean up yet
+            self.active_backend_config = None;
+            if let Err(stop_error) = backend.stop() {
+                eprintln!("Failed to stop the {} backend: {:?}", game.vr_backend, stop_error);
+            }
+            return Err(error);
         }
+        self.active_backend_config.replace(backend_config);
+
+        Ok(PreparedLaunch {
+            steam_app,
+            compat_info: compat_version.map(|v| ProtonLaunchInfo {
+                version: v,
+                use_pressure_vessel: game.pressure_vessel,
+            }),
+            prefix_id,
+            launch_options,
+            modifiers,
+            backend,
+        })
+    }
 
-        // Launch the game
+    /// The last step of a launch, once the backend is up and running
+    pub fn spawn_game(&mut self, game: Game, steam_app: SteamApp, compat_info: Option<ProtonLaunchInfo>, modifiers: Vec<Box<dyn LaunchModifier>>, launch_options: &LaunchOptions, vr_device_serial: Option<String>) -> anyhow::Result<()> {
         let game_log_channel = self.log_session.as_mut().unwrap().create_channel("game")?;
-        let compat_info = compat_version.map(|v| ProtonLaunchInfo {
-            version: v,
-            use_pressure_vessel: game.pressure_vessel,
-        });
         let process_handle = self.launcher.launch_app(
             &steam_app,
             compat_info,
             modifiers,
-            self.sock_tx.clone(),
+            launch_options,
+            game.use_systemd_scope,
             game_log_channel,
         )?;
-        
+
         println!("Started main game process. PID: {}", process_handle.get_pid());
         self.active_game_session.replace(GameSession {
             game,
@@ -219,61 +368,172 @@ impl AppState {
             start_time_epoch: SystemTime::now()
                 .duration_since(UNIX_EPOCH)?
                 .as_secs(),
-            vr_device_serial: start_info.vr_device_serial,
+            vr_device_serial,
+            keep_backend_on_exit: false,
+            stop_requested: false,
         });
 
-        let message = format!("active:{}", serde_json::to_string(self.active_game_session.as_ref().unwrap())?).into();
-        _ = self.sock_tx.send(message);
+        _ = self.sock_tx.send(ServerEvent::Active(Box::new(self.active_game_session.as_ref().unwrap().info())));
 
         Ok(())
     }
 
-    pub fn kill_active_game(&mut self) -> anyhow::Result<()> {
-        let active_session = self.active_game_session.as_mut().unwrap();
+    /// Marks the running game as stopping and returns its processes, which the caller terminates
+    /// without holding the lock. The session ends once the launcher sees the game exit, see
+    /// [`AppState::game_process_died`]. `keep_backend` leaves the backend and overlay running for the next game.
+    pub fn begin_stopping_game(&mut self, keep_backend: bool) -> Option<GameProcessTree> {
+        let session = self.active_game_session.as_mut()?;
+        session.keep_backend_on_exit = keep_backend;
+        session.stop_requested = true;
+        let process_tree = session.process_handle.process_tree();
+
+        if let Some(launch_id) = self.active_launch.as_ref().map(|l| l.progress.launch_id.clone()) {
+            self.set_launch_state(&launch_id, LaunchState::Stopping, None);
+        }
+
+        Some(process_tree)
+    }
 
-        let mut sys = System::new_all();
-        sys.refresh_all();
+    pub fn game_process_died(&mut self, process_token: &Uuid, exit_status: Option<ExitStatus>) -> anyhow::Result<()> {
+        match &self.active_game_session {
+            Some(session) if session.process_handle.get_process_token() == process_token => self.end_game_session(exit_status),
+            // The session has already ended, e.g. the launcher noticed the exit while a kill was in progress
+            _ => Ok(()),
+        }
+    }
 
-        for (pid, process) in sys.processes() {
-            let environ = process.environ()
-                .into_iter()
-                .map(|s| s.clone().into_string().unwrap())
-                .flat_map(|s| s.split_once('=').map(|(k, v)| (k.to_string(), v.to_string())))
-                .collect::<HashMap<_, _>>();
-
-            if let Some(process_token) = environ.get("SVRL_TOKEN") {
-                if *process_token == active_session.process_handle.get_process_token().to_string() {
-                    let pid = nix::unistd::Pid::from_raw(pid.as_u32() as pid_t);
-                    _ = nix::sys::signal::kill(pid, Some(nix::sys::signal::Signal::SIGTERM));
-                }
+    fn end_game_session(&mut self, exit_status: Option<ExitStatus>) -> anyhow::Result<()> {
+        if let Some(launch) = &self.active_launch
+            && launch.progress.state != LaunchState::Stopping {
+            let launch_id = launch.progress.launch_id.clone();
+            self.set_launch_state(&launch_id, LaunchState::Stopping, None);
+        }
+
+        let mut keep_backend = false;
+        if let Some(session) = self.active_game_session.take() {
+            keep_backend = session.keep_backend_on_exit;
+            let crash = match session.stop_requested {
+                true => None,
+                false => self.report_crash(&session, exit_status),
+            };
+            if let Err(error) = self.record_session(&session, exit_status, crash.as_ref()) {
+                eprintln!("Failed to record the game session: {:?}", error);
+            }
+            if let Some(crash) = crash {
+                _ = self.sock_tx.send(ServerEvent::GameCrashed(crash));
             }
         }
+        _ = self.sock_tx.send(ServerEvent::Inactive);
+        self.active_launch = None;
+        self.backend_health = None;
+
+        if !keep_backend {
+            if let Some(mut active_backend) = self.active_backend.take() {
+                active_backend.stop()?;
+            }
+            self.active_backend_config = None;
 
-        self.game_process_died()
+            self.overlay_manager.stop()?;
+        }
+
+        Ok(())
     }
 
-    pub fn game_process_died(&mut self) -> anyhow::Result<()> {
-        _ = self.active_game_session.take();
-        _ = self.sock_tx.send("inactive".into());
+    /// Classifies the exit of a game the launcher did not stop, and bundles its diagnostics if it has crashed
+    fn report_crash(&self, session: &GameSession, exit_status: Option<ExitStatus>) -> Option<CrashInfo> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+        let run_time_secs = now.saturating_sub(session.start_time_epoch);
+        let kind = diagnostics::classify_exit(exit_status, run_time_secs)?;
+        println!("The game {} has crashed ({:?}, exit status {:?})", session.game.id, kind, exit_status);
+
+        let mut crash = CrashInfo {
+            game_id: session.game.id.clone(),
+            kind,
+            exit_code: exit_status.and_then(|s| s.code()),
+            exit_signal: exit_status.and_then(|s| s.signal()),
+            run_time_secs,
+            diagnostics_bundle: None,
+        };
 
-        if let Some(active_backend) = self.active_backend.as_mut() {
-            active_backend.stop()?;
+        let report = DiagnosticsReport {
+            crash: &crash,
+            game: &session.game,
+            proton_version: session.game.proton_version.as_deref(),
+            vr_backend: &session.game.vr_backend,
+            vr_backend_args: &session.game.vr_backend_args,
+            vr_device_serial: session.vr_device_serial.as_deref(),
+            system: SystemReport::collect(),
+        };
+        let log_files = self.log_session.as_ref().map(|s| s.channel_files()).unwrap_or_default();
+        let bundle = diagnostics::diagnostics_dir().and_then(|output_dir| {
+            diagnostics::write_bundle(&output_dir, &report, session.process_handle.launch_environment(), &log_files)
+        });
+        match bundle {
+            Ok(file_name) => crash.diagnostics_bundle = Some(file_name),
+            Err(error) => eprintln!("Failed to create the diagnostics bundle: {:?}", error),
         }
 
-        self.overlay_manager.stop()?;
+        Some(crash)
+    }
+
+    fn record_session(&self, session: &GameSession, exit_status: Option<ExitStatus>, crash: Option<&CrashInfo>) -> anyhow::Result<()> {
+        let end_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)?
+            .as_secs();
+
+        let resource_usage = session.process_handle.resource_usage();
+        let new_session = NewSession {
+            game_id: session.game.id.clone(),
+            start_time: session.start_time_epoch as i64,
+            end_time: end_time as i64,
+            exit_code: exit_status.and_then(|s| s.code()),
+            exit_signal: exit_status.and_then(|s| s.signal()),
+            vr_backend: session.game.vr_backend.clone(),
+            device_serial: session.vr_device_serial.clone(),
+            log_archive: self.log_session.as_ref().map(|s| s.archive_file_name()),
+            cpu_time_ms: resource_usage.cpu_time_ms,
+            peak_memory_bytes: resource_usage.peak_memory_bytes,
+            crash_kind: crash.map(|c| c.kind.as_str().to_string()),
+            diagnostics_bundle: crash.and_then(|c| c.diagnostics_bundle.clone()),
+        };
+
+        let connection = &mut self.db_pool.get()?;
+        diesel::insert_into(sessions)
+            .values(&new_session)
+            .execute(connection)?;
 
         Ok(())
     }
 
-    pub fn start_log_session(&mut self) -> anyhow::Result<()> {
-        if let Some(mut log_session) = self.log_session.take() {
-            log_session.shutdown()?;
+    /// Everything a freshly connected client needs to render the current state
+    pub async fn snapshot_async(&self) -> StateSnapshot {
+        let devices = self.device_manager.lock().await.list_devices_async().await;
+
+        StateSnapshot {
+            active_session: self.active_game_session.as_ref().map(|s| s.info()),
+            launch: self.active_launch.as_ref().map(|l| l.progress.clone()),
+            launch_queue: self.launch_queue.iter().cloned().collect(),
+            backend_health: self.backend_health.clone(),
+            battery: self.battery_monitor.get_battery_info_async().await,
+            default_input: self.audio_api.get_default_input_device(),
+            default_output: self.audio_api.get_default_output_device(),
+            devices,
         }
+    }
 
+    pub fn start_log_session(&mut self, keep_backend_channels: bool) -> anyhow::Result<()> {
         let logs_dir = env::current_exe()?
             .parent().unwrap()
             .join("logs");
         let mut session = LogSession::new(logs_dir);
+
+        if let Some(mut log_session) = self.log_session.take() {
+            if keep_backend_channels {
+                log_session.hand_over_channels(&mut session, |name| name.starts_with("vr_backend") || name == "overlay");
+            }
+            log_session.shutdown()?;
+        }
+
         session.archive_old_files()?;
         self.log_session.replace(session);
 
@@ -289,10 +549,12 @@ impl AppState {
             log_session.shutdown()?;
         }
 
-        if let Some(_) = &self.active_game_session {
-            self.kill_active_game()?;
+        if let Some(process_tree) = self.begin_stopping_game(false) {
+            let exit_status = process_tree.terminate(DEFAULT_GRACE_PERIOD).await;
+            self.game_process_died(&process_tree.process_token, exit_status)?;
         }
-        
+
+
         let device_manager = self.device_manager.lock().await;
         device_manager.disconnect_tcpip()?;
 
@@ -19,6 +19,7 @@ use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
 use tokio::sync::broadcast;
 use tokio::sync::broadcast::Sender;
+use ts_rs::TS;
 
 #[derive(Debug, Clone, Serialize)]
 pub enum DeviceChangeEvent {
@@ -397,7 +398,8 @@ impl PipeWireManager {
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
 pub struct AudioDevice {
     pub id: u32,
     pub name: String,
@@ -405,6 +407,7 @@ pub struct AudioDevice {
     pub is_default: bool,
 
     #[serde(skip_serializing)]
+    #[ts(skip)]
     pub pod_bytes: Option<Vec<u8>>,
     pub volume: u8,
     pub is_muted: bool,
@@ -0,0 +1,61 @@
+use std::time::Duration;
+use anyhow::Context;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const DEFAULT_API_URL: &str = "http://127.0.0.1:8082";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A tiny client for the HTTP API that the ALVR streamer serves on localhost.
+/// Only plain `http://` URLs are supported, since the API never leaves the machine.
+#[derive(Debug, Clone)]
+pub struct AlvrApiClient {
+    host: String,
+    port: u16,
+}
+
+impl AlvrApiClient {
+    pub fn new(base_url: &str) -> anyhow::Result<Self> {
+        let authority = base_url.strip_prefix("http://")
+            .ok_or_else(|| anyhow::anyhow!("The ALVR API URL must start with http://"))?
+            .trim_end_matches('/');
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse::<u16>().context("Invalid ALVR API port")?),
+            None => (authority, 80),
+        };
+
+        Ok(Self { host: host.to_string(), port })
+    }
+
+    /// Returns the streamer version, or `None` if the API is not reachable yet
+    pub async fn get_version(&self) -> anyhow::Result<Option<String>> {
+        match self.get("/api/version").await {
+            Ok((200, body)) => Ok(Some(body.trim().trim_matches('"').to_string())),
+            Ok((status, _)) => Err(anyhow::anyhow!("The ALVR API responded with status {}", status)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    async fn get(&self, path: &str) -> anyhow::Result<(u16, String)> {
+        tokio::time::timeout(REQUEST_TIMEOUT, async {
+            let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+            let request = format!("GET {} HTTP/1.1\r\nHost: {}:{}\r\nConnection: close\r\n\r\n", path, self.host, self.port);
+            stream.write_all(request.as_bytes()).await?;
+
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).await?;
+            Self::parse_response(&String::from_utf8_lossy(&response))
+        }).await?
+    }
+
+    fn parse_response(response: &str) -> anyhow::Result<(u16, String)> {
+        let (head, body) = response.split_once("\r\n\r\n")
+            .ok_or_else(|| anyhow::anyhow!("Malformed HTTP response"))?;
+        let status = head.lines().next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|status| status.parse::<u16>().ok())
+            .ok_or_else(|| anyhow::anyhow!("Malformed HTTP status line"))?;
+
+        Ok((status, body.to_string()))
+    }
+}
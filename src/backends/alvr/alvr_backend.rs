@@ -0,0 +1,231 @@
+use crate::adb::device_manager::DeviceManager;
+use crate::audio_api::AudioDevice;
+use crate::backends::alvr::alvr_api::{AlvrApiClient, DEFAULT_API_URL};
+use crate::backends::alvr::alvr_launch_modifier::AlvrLaunchModifier;
+use crate::backends::{BackendStartInfo, VRBackend};
+use crate::logging::log_channel::LogChannel;
+use crate::steam::launch_modifiers::LaunchModifier;
+use crate::TokioMutex;
+use anyhow::bail;
+use async_trait::async_trait;
+use std::env;
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use sysinfo::{Pid, System};
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+
+const STEAMVR_APP_ID: u32 = 250820;
+const CLIENT_PACKAGE_NAME: &str = "alvr.client.stable";
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+/// The streamer is a SteamVR driver, so it runs inside these processes and not the dashboard's
+const STEAMVR_PROCESS_NAMES: [&str; 2] = ["vrserver", "vrmonitor"];
+
+pub struct AlvrBackend {
+    dashboard_binary_path: PathBuf,
+    api_client: AlvrApiClient,
+    dashboard_pid: Option<u32>,
+    dashboard_wait_handle: Option<JoinHandle<()>>,
+}
+
+#[async_trait]
+impl VRBackend for AlvrBackend {
+    async fn start_async(&mut self, backend_log_channel: Arc<Mutex<LogChannel>>, device_manager: Arc<TokioMutex<DeviceManager>>) -> anyhow::Result<BackendStartInfo> {
+        // The dashboard may already be running if the user started it by hand
+        let needs_new_dashboard_process = !self.is_ready().await?;
+
+        if needs_new_dashboard_process {
+            println!("Starting ALVR dashboard [{}]...", self.dashboard_binary_path.display());
+            let mut dashboard_process = Command::new(self.dashboard_binary_path.as_os_str())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()?;
+            LogChannel::connect_tokio(backend_log_channel.clone(), &mut dashboard_process);
+
+            let dashboard_pid = dashboard_process.id().unwrap();
+            let started_at = tokio::time::Instant::now();
+            while !self.is_ready().await? {
+                if let Some(status) = dashboard_process.try_wait()? {
+                    bail!("The ALVR dashboard exited unexpectedly with status {}", status);
+                }
+                if started_at.elapsed() > STARTUP_TIMEOUT {
+                    _ = dashboard_process.start_kill();
+                    bail!("The ALVR streamer did not become ready within {} seconds", STARTUP_TIMEOUT.as_secs());
+                }
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            println!("Started ALVR dashboard process. PID: {}", dashboard_pid);
+
+            self.dashboard_pid.replace(dashboard_pid);
+            self.dashboard_wait_handle.replace(tokio::task::spawn(async move {
+                let status = dashboard_process.wait().await;
+                println!("The ALVR dashboard has exited with status {:?}", status);
+            }));
+        }
+
+        self.reconnect_async(device_manager.clone()).await?;
+
+        let device_manager = device_manager.lock().await;
+        let active_device = device_manager.get_selected_device_async().await?
+            .ok_or_else(|| anyhow::anyhow!("No VR device is selected"))?;
+
+        Ok(BackendStartInfo {
+            vr_device_serial: Some(active_device.usb_serial),
+            vr_device_ip: active_device.ip_address,
+            was_restarted: needs_new_dashboard_process,
+        })
+    }
+
+    async fn reconnect_async(&mut self, device_manager: Arc<TokioMutex<DeviceManager>>) -> anyhow::Result<()> {
+        println!("Starting ALVR client...");
+        let device_manager = device_manager.lock().await;
+        let active_device = device_manager.get_selected_device_async().await?
+            .ok_or_else(|| anyhow::anyhow!("No VR device is selected"))?;
+
+        active_device.adb_shell_command(&[
+            "monkey",
+            "-p", CLIENT_PACKAGE_NAME,
+            "-c", "android.intent.category.LAUNCHER",
+            "1",
+        ])?;
+
+        Ok(())
+    }
+
+    async fn is_ready(&self) -> anyhow::Result<bool> {
+        Ok(self.api_client.get_version().await?.is_some())
+    }
+
+    fn stop(&mut self) -> anyhow::Result<()> {
+        // A dashboard that the user started by hand is left running, together with its streamer
+        if let Some(dashboard_pid) = self.dashboard_pid.take() {
+            let mut sys = System::new_all();
+            sys.refresh_all();
+            if let Some(process) = sys.process(Pid::from_u32(dashboard_pid)) {
+                process.kill_with(sysinfo::Signal::Term);
+            }
+
+            for process_name in STEAMVR_PROCESS_NAMES {
+                for process in sys.processes_by_name(OsStr::new(process_name)) {
+                    println!("Stopping SteamVR process {}: {:?}", process.pid(), process.name());
+                    process.kill_with(sysinfo::Signal::Term);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_matching_audio_device(&self, device: &AudioDevice) -> bool {
+        device.description.to_lowercase().contains("alvr")
+            || device.name.to_lowercase().contains("alvr")
+    }
+
+    fn add_modifiers(&self, list: &mut Vec<Box<dyn LaunchModifier>>) -> anyhow::Result<()> {
+        list.insert(0, Box::new(AlvrLaunchModifier::new(Self::locate_steamvr_manifest()?)));
+
+        Ok(())
+    }
+
+    fn is_running(&self) -> bool {
+        self.dashboard_wait_handle.as_ref().is_none_or(|handle| !handle.is_finished())
+    }
+}
+
+impl AlvrBackend {
+    /// `args` is an optional path to the `alvr_dashboard` binary. The streamer API
+    /// is expected on `ALVR_API_URL`, or on ALVR's default port when it is not set.
+    pub fn new(args: String) -> anyhow::Result<AlvrBackend> {
+        let api_url = env::var("ALVR_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+        let server_binary = match args.is_empty() {
+            true => None,
+            false => Some(PathBuf::from(args)),
+        };
+
+        Self::with_api_url(Self::locate_dashboard_binary_path(server_binary)?, &api_url)
+    }
+
+    pub fn with_api_url(dashboard_binary_path: PathBuf, api_url: &str) -> anyhow::Result<AlvrBackend> {
+        Ok(AlvrBackend {
+            dashboard_binary_path,
+            api_client: AlvrApiClient::new(api_url)?,
+            dashboard_pid: None,
+            dashboard_wait_handle: None,
+        })
+    }
+
+    pub fn locate_dashboard_binary_path(dashboard_binary: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+        if let Some(dashboard_binary) = dashboard_binary {
+            if dashboard_binary.is_file() {
+                return Ok(dashboard_binary);
+            }
+            bail!("The ALVR dashboard binary '{}' does not exist", dashboard_binary.display());
+        }
+
+        if let Ok(path) = which::which("alvr_dashboard") {
+            return Ok(path);
+        }
+
+        bail!("The ALVR dashboard binary was not found")
+    }
+
+    fn locate_steamvr_manifest() -> anyhow::Result<PathBuf> {
+        let steam_dir = steamlocate::SteamDir::locate()?;
+        let (steamvr_app, library) = steam_dir.find_app(STEAMVR_APP_ID)?
+            .ok_or_else(|| anyhow::anyhow!("SteamVR is not installed, but it is required by ALVR"))?;
+
+        let manifest_path = library.resolve_app_dir(&steamvr_app).join("steamxr_linux64.json");
+        if !manifest_path.exists() {
+            bail!("The SteamVR OpenXR manifest was not found");
+        }
+
+        Ok(manifest_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use axum::Router;
+
+    async fn start_stub_api(version_status: axum::http::StatusCode) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let app = Router::new()
+            .route("/api/version", get(move || async move { (version_status, "\"20.11.1\"") }));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_ready_when_api_responds() {
+        let api_url = start_stub_api(axum::http::StatusCode::OK).await;
+        let backend = AlvrBackend::with_api_url(PathBuf::from("alvr_dashboard"), &api_url).unwrap();
+
+        assert!(backend.is_ready().await.unwrap());
+        assert_eq!(backend.api_client.get_version().await.unwrap().as_deref(), Some("20.11.1"));
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_without_api() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let api_url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let backend = AlvrBackend::with_api_url(PathBuf::from("alvr_dashboard"), &api_url).unwrap();
+
+        assert!(!backend.is_ready().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_api_error_is_reported() {
+        let api_url = start_stub_api(axum::http::StatusCode::INTERNAL_SERVER_ERROR).await;
+        let backend = AlvrBackend::with_api_url(PathBuf::from("alvr_dashboard"), &api_url).unwrap();
+
+        assert!(backend.is_ready().await.is_err());
+    }
+}
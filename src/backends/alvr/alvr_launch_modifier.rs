@@ -0,0 +1,34 @@
+use crate::steam::launch_modifiers::LaunchModifier;
+use crate::steam::steam_interface::{ProtonVersion, SteamApp};
+use std::path::PathBuf;
+use std::{env, fs};
+use tokio::process;
+
+/// ALVR streams through SteamVR, so games have to use SteamVR as their OpenXR runtime.
+/// OpenVR games find SteamVR through `openvrpaths.vrpath`, which SteamVR maintains itself.
+pub struct AlvrLaunchModifier {
+    steamvr_manifest_path: PathBuf,
+}
+
+impl AlvrLaunchModifier {
+    pub fn new(steamvr_manifest_path: PathBuf) -> Self {
+        Self { steamvr_manifest_path }
+    }
+}
+
+impl LaunchModifier for AlvrLaunchModifier {
+    fn apply(&self, command: &mut process::Command, _app: &SteamApp, _compat_version: Option<&ProtonVersion>) -> anyhow::Result<()> {
+        command.env("PRESSURE_VESSEL_IMPORT_OPENXR_1_RUNTIMES", "1");
+
+        let openxr_target_path = env::home_dir().unwrap()
+            .join(".config/openxr/1/active_runtime.json");
+        fs::create_dir_all(openxr_target_path.parent().unwrap())?;
+        if openxr_target_path.exists() || openxr_target_path.is_symlink() {
+            fs::remove_file(&openxr_target_path)?;
+        }
+
+        std::os::unix::fs::symlink(&self.steamvr_manifest_path, &openxr_target_path)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,3 @@
+pub mod alvr_api;
+pub mod alvr_backend;
+pub mod alvr_launch_modifier;
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+use crate::command_parser::tokenize_command;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -59,12 +61,100 @@ pub struct OvrComp {
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Environment {
-    #[serde(rename = "LD_LIBRARY_PATH")]
+    #[serde(rename = "LD_LIBRARY_PATH", default)]
     pub ld_library_path: String,
-    #[serde(rename = "U_PACING_APP_USE_MIN_FRAME_PERIOD")]
+    #[serde(rename = "U_PACING_APP_USE_MIN_FRAME_PERIOD", default)]
     pub u_pacing_app_use_min_frame_period: String,
-    #[serde(rename = "XRT_CURATED_GUI")]
+    #[serde(rename = "XRT_CURATED_GUI", default)]
     pub xrt_curated_gui: String,
-    #[serde(rename = "XRT_DEBUG_GUI")]
+    #[serde(rename = "XRT_DEBUG_GUI", default)]
     pub xrt_debug_gui: String,
-}
\ No newline at end of file
+    /// Monado profiles carry many more variables, like `XRT_COMPOSITOR_COMPUTE` or `LH_DRIVER`
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+impl Environment {
+    pub fn to_vars(&self) -> Vec<(String, String)> {
+        let mut vars = vec![
+            ("LD_LIBRARY_PATH".to_string(), self.ld_library_path.clone()),
+            ("U_PACING_APP_USE_MIN_FRAME_PERIOD".to_string(), self.u_pacing_app_use_min_frame_period.clone()),
+            ("XRT_CURATED_GUI".to_string(), self.xrt_curated_gui.clone()),
+            ("XRT_DEBUG_GUI".to_string(), self.xrt_debug_gui.clone()),
+        ];
+        vars.retain(|(_, value)| !value.is_empty());
+        vars.extend(self.other.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        vars
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum XrServiceType {
+    WiVRn,
+    Monado,
+    Unknown(String),
+}
+
+impl EnvisionUserProfile {
+    pub fn service_type(&self) -> XrServiceType {
+        match self.xrservice_type.to_lowercase().as_str() {
+            "wivrn" => XrServiceType::WiVRn,
+            "monado" => XrServiceType::Monado,
+            _ => XrServiceType::Unknown(self.xrservice_type.clone()),
+        }
+    }
+
+    /// The arguments of the XR service, which may be quoted like in a shell
+    pub fn launch_args(&self) -> anyhow::Result<Vec<String>> {
+        tokenize_command(&self.xrservice_launch_options)
+            .map_err(|err| anyhow::anyhow!("Could not parse the launch options of the Envision profile: {}", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monado_environment_keeps_extra_vars() {
+        let environment = serde_json::from_str::<Environment>(r#"{
+            "XRT_COMPOSITOR_COMPUTE": "1",
+            "LH_DRIVER": "steamvr",
+            "LD_LIBRARY_PATH": "/prefix/lib"
+        }"#).unwrap();
+
+        let mut vars = environment.to_vars();
+        vars.sort();
+        assert_eq!(vars, vec![
+            ("LD_LIBRARY_PATH".to_string(), "/prefix/lib".to_string()),
+            ("LH_DRIVER".to_string(), "steamvr".to_string()),
+            ("XRT_COMPOSITOR_COMPUTE".to_string(), "1".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_service_type() {
+        let mut profile = EnvisionUserProfile::default();
+        for (xrservice_type, expected) in [
+            ("Wivrn", XrServiceType::WiVRn),
+            ("Monado", XrServiceType::Monado),
+            ("Other", XrServiceType::Unknown("Other".into())),
+        ] {
+            profile.xrservice_type = xrservice_type.into();
+            assert_eq!(profile.service_type(), expected);
+        }
+    }
+
+    #[test]
+    fn test_launch_args() {
+        let mut profile = EnvisionUserProfile {
+            xrservice_launch_options: r#"--config "/home/user/my config.json"  -v"#.into(),
+            ..Default::default()
+        };
+        assert_eq!(profile.launch_args().unwrap(), ["--config", "/home/user/my config.json", "-v"]);
+
+        profile.xrservice_launch_options = r#"--config "unclosed"#.into();
+        assert!(profile.launch_args().is_err());
+    }
+}
@@ -8,6 +8,10 @@ use crate::TokioMutex;
 
 pub mod wivrn;
 pub mod envision;
+pub mod alvr;
+pub mod monado;
+pub mod registry;
+pub mod supervisor;
 
 #[async_trait]
 pub trait VRBackend: Send {
@@ -17,20 +21,33 @@ pub trait VRBackend: Send {
     fn stop(&mut self) -> anyhow::Result<()>;
     fn is_matching_audio_device(&self, device: &AudioDevice) -> bool;
     fn add_modifiers(&self, list: &mut Vec<Box<dyn LaunchModifier>>) -> anyhow::Result<()>;
+
+    /// Whether the backend streams to a standalone headset that is controlled over ADB
+    fn requires_adb_device(&self) -> bool {
+        true
+    }
+
+    /// Whether the server process started by this backend is still alive. Backends that did not
+    /// start a process of their own report `true`, see `backends::supervisor`.
+    fn is_running(&self) -> bool {
+        true
+    }
 }
 
 #[allow(dead_code)]
 pub struct BackendStartInfo {
-    pub vr_device_serial: String,
+    /// Not known for backends that do not talk to the headset themselves, like Monado
+    pub vr_device_serial: Option<String>,
     pub vr_device_ip: Option<String>,
     pub was_restarted: bool,
 }
 
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum BackendType {
     Unknown,
     WiVRn,
     Envision,
     ALVR,
-}
\ No newline at end of file
+    Monado,
+}
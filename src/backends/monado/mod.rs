@@ -0,0 +1,2 @@
+pub mod monado_backend;
+pub mod monado_launch_modifier;
@@ -0,0 +1,177 @@
+use crate::adb::device_manager::DeviceManager;
+use crate::audio_api::AudioDevice;
+use crate::backends::monado::monado_launch_modifier::MonadoLaunchModifier;
+use crate::backends::{BackendStartInfo, VRBackend};
+use crate::logging::log_channel::LogChannel;
+use crate::steam::launch_modifiers::LaunchModifier;
+use crate::TokioMutex;
+use anyhow::bail;
+use async_trait::async_trait;
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use sysinfo::{Pid, System};
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Runs a plain Monado service, for headsets that are plugged into the PC directly
+pub struct MonadoBackend {
+    prefix: PathBuf,
+    environment: Vec<(String, String)>,
+    launch_args: Vec<String>,
+    service_pid: Option<u32>,
+    service_wait_handle: Option<JoinHandle<()>>,
+}
+
+#[async_trait]
+impl VRBackend for MonadoBackend {
+    async fn start_async(&mut self, backend_log_channel: Arc<Mutex<LogChannel>>, _device_manager: Arc<TokioMutex<DeviceManager>>) -> anyhow::Result<BackendStartInfo> {
+        let mut sys = System::new_all();
+        sys.refresh_all();
+        let needs_new_service_process = self.service_pid
+            .is_none_or(|pid| sys.process(Pid::from_u32(pid)).is_none());
+
+        if needs_new_service_process {
+            for process in sys.processes_by_name(OsStr::new("monado-service")) {
+                println!("Killing existing Monado service process {}: {:?}", process.pid(), process.name());
+                if let Err(e) = process.kill_and_wait() {
+                    println!("Failed to kill existing Monado service process: {:?}", e);
+                }
+            }
+
+            // A crashed service leaves its socket behind, which would look like a ready service
+            let socket_path = Self::get_socket_path()?;
+            if socket_path.exists() {
+                std::fs::remove_file(&socket_path)?;
+            }
+
+            let service_binary_path = self.prefix.join("bin/monado-service");
+            println!("Starting Monado service [{}]...", service_binary_path.display());
+            let mut service_process = Command::new(&service_binary_path)
+                .args(&self.launch_args)
+                .envs(self.environment.iter().map(|(k, v)| (k, v)))
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()?;
+            LogChannel::connect_tokio(backend_log_channel.clone(), &mut service_process);
+
+            let service_pid = service_process.id().unwrap();
+            let started_at = tokio::time::Instant::now();
+            while !self.is_ready().await? {
+                if let Some(status) = service_process.try_wait()? {
+                    let log_channel = backend_log_channel.lock()
+                        .map_err(|e| anyhow::anyhow!("Failed to lock Monado log channel: {}", e))?;
+                    let last_error_line = log_channel.get_stderr_lines().last();
+                    bail!("The Monado service exited unexpectedly with status {}: {:?}",
+                        status,
+                        last_error_line.unwrap_or(&"Unknown error".into()));
+                }
+                if started_at.elapsed() > STARTUP_TIMEOUT {
+                    _ = service_process.start_kill();
+                    bail!("The Monado service did not become ready within {} seconds", STARTUP_TIMEOUT.as_secs());
+                }
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+            println!("Started Monado service process. PID: {}", service_pid);
+
+            self.service_pid.replace(service_pid);
+            self.service_wait_handle.replace(tokio::task::spawn(async move {
+                let status = service_process.wait().await;
+                println!("The Monado service has exited with status {:?}", status);
+            }));
+        }
+
+        Ok(BackendStartInfo {
+            vr_device_serial: None,
+            vr_device_ip: None,
+            was_restarted: needs_new_service_process,
+        })
+    }
+
+    async fn reconnect_async(&mut self, _device_manager: Arc<TokioMutex<DeviceManager>>) -> anyhow::Result<()> {
+        // Wired headsets have no client app that would need to be restarted
+        Ok(())
+    }
+
+    async fn is_ready(&self) -> anyhow::Result<bool> {
+        Ok(Self::get_socket_path()?.exists())
+    }
+
+    fn stop(&mut self) -> anyhow::Result<()> {
+        if let Some(service_pid) = self.service_pid.take() {
+            let mut sys = System::new_all();
+            sys.refresh_all();
+            if let Some(process) = sys.process(Pid::from_u32(service_pid)) {
+                process.kill_with(sysinfo::Signal::Term);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_matching_audio_device(&self, _device: &AudioDevice) -> bool {
+        // Monado does not create virtual audio devices, the headset brings its own
+        false
+    }
+
+    fn add_modifiers(&self, list: &mut Vec<Box<dyn LaunchModifier>>) -> anyhow::Result<()> {
+        let manifest_path = self.prefix.join("share/openxr/1/openxr_monado.json");
+        if !manifest_path.exists() {
+            bail!("Monado manifest file not found in {}", self.prefix.display());
+        }
+        list.insert(0, Box::new(MonadoLaunchModifier::new(manifest_path)));
+
+        Ok(())
+    }
+
+    fn is_running(&self) -> bool {
+        self.service_wait_handle.as_ref().is_none_or(|handle| !handle.is_finished())
+    }
+
+    fn requires_adb_device(&self) -> bool {
+        false
+    }
+}
+
+impl MonadoBackend {
+    pub fn new(prefix: PathBuf, environment: Vec<(String, String)>, launch_args: Vec<String>) -> anyhow::Result<MonadoBackend> {
+        if !prefix.join("bin/monado-service").is_file() {
+            bail!("No Monado service was found in {}", prefix.display());
+        }
+
+        Ok(MonadoBackend {
+            prefix,
+            environment,
+            launch_args,
+            service_pid: None,
+            service_wait_handle: None,
+        })
+    }
+
+    /// The install prefix of Monado, `/usr` for a packaged Monado that is on the `PATH`
+    pub fn locate_prefix(prefix: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+        if let Some(prefix) = prefix {
+            if !prefix.join("bin/monado-service").is_file() {
+                bail!("No Monado service was found in {}", prefix.display());
+            }
+            return Ok(prefix);
+        }
+
+        // Otherwise, derive the prefix from the path of the "monado-service" command
+        if let Ok(path) = which::which("monado-service")
+            && let Some(prefix) = path.parent().and_then(|bin| bin.parent()) {
+            return Ok(prefix.to_path_buf());
+        }
+
+        bail!("The Monado service binary was not found")
+    }
+
+    fn get_socket_path() -> anyhow::Result<PathBuf> {
+        let xdg_runtime_dir = std::env::var("XDG_RUNTIME_DIR")?;
+        Ok(PathBuf::from(xdg_runtime_dir).join("monado_comp_ipc"))
+    }
+}
@@ -0,0 +1,227 @@
+use crate::backends::alvr::alvr_backend::AlvrBackend;
+use crate::backends::envision::envision_backend::EnvisionBackend;
+use crate::backends::monado::monado_backend::MonadoBackend;
+use crate::backends::wivrn::wivrn_backend::WiVRnBackend;
+use crate::backends::{BackendType, VRBackend};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+pub type BackendFactory = fn(&str) -> anyhow::Result<Box<dyn VRBackend + Send + Sync>>;
+
+#[derive(Debug, Clone, Copy, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub enum BackendArgsKind {
+    /// The backend takes no arguments
+    None,
+    /// An optional path to a custom server binary
+    BinaryPath,
+    /// The UUID of an Envision profile
+    EnvisionProfile,
+    /// An optional install prefix, like `/usr` or `~/.local`
+    Prefix,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct BackendArgsSchema {
+    pub kind: BackendArgsKind,
+    pub required: bool,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct BackendArgChoice {
+    pub value: String,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct BackendInfo {
+    pub name: String,
+    pub display_name: String,
+    pub args: BackendArgsSchema,
+    pub arg_choices: Vec<BackendArgChoice>,
+    pub is_detected: bool,
+    pub detection_error: Option<String>,
+}
+
+pub struct BackendRegistration {
+    pub name: &'static str,
+    pub display_name: &'static str,
+    pub backend_type: BackendType,
+    pub args_kind: BackendArgsKind,
+    pub args_required: bool,
+    pub args_description: &'static str,
+    pub factory: BackendFactory,
+    /// Checks that the backend is installed, without starting anything
+    pub detect: fn() -> anyhow::Result<()>,
+    /// Checks the per-game arguments when a game is saved
+    pub validate_args: fn(&str) -> anyhow::Result<()>,
+    pub list_arg_choices: fn() -> Vec<BackendArgChoice>,
+}
+
+/// Every VR backend that games can be launched with, looked up by the `vr_backend` column
+#[derive(Default)]
+pub struct BackendRegistry {
+    backends: Vec<BackendRegistration>,
+}
+
+impl BackendRegistry {
+    pub fn new() -> Self {
+        let mut registry = Self::default();
+
+        registry.register(BackendRegistration {
+            name: "wivrn",
+            display_name: "WiVRn",
+            backend_type: BackendType::WiVRn,
+            args_kind: BackendArgsKind::BinaryPath,
+            args_required: false,
+            args_description: "Path to a custom wivrn-server binary",
+            factory: |args| Ok(Box::new(WiVRnBackend::new(optional_path(args))?)),
+            detect: || WiVRnBackend::locate_server_binary_path(None).map(|_| ()),
+            validate_args: |args| validate_optional_file(args, "WiVRn server binary"),
+            list_arg_choices: Vec::new,
+        });
+
+        registry.register(BackendRegistration {
+            name: "envision",
+            display_name: "Envision",
+            backend_type: BackendType::Envision,
+            args_kind: BackendArgsKind::EnvisionProfile,
+            args_required: true,
+            args_description: "UUID of the Envision profile to launch",
+            factory: |args| Ok(Box::new(EnvisionBackend::new(args.to_string())?)),
+            detect: || EnvisionBackend::load_configuration().map(|_| ()),
+            validate_args: |args| EnvisionBackend::find_profile(args).map(|_| ()),
+            list_arg_choices: || EnvisionBackend::load_configuration()
+                .map(|config| config.user_profiles.into_iter()
+                    .map(|profile| BackendArgChoice {
+                        value: profile.uuid,
+                        label: profile.name,
+                    })
+                    .collect())
+                .unwrap_or_default(),
+        });
+
+        registry.register(BackendRegistration {
+            name: "alvr",
+            display_name: "ALVR",
+            backend_type: BackendType::ALVR,
+            args_kind: BackendArgsKind::BinaryPath,
+            args_required: false,
+            args_description: "Path to a custom alvr_dashboard binary",
+            factory: |args| Ok(Box::new(AlvrBackend::new(args.to_string())?)),
+            detect: || AlvrBackend::locate_dashboard_binary_path(None).map(|_| ()),
+            validate_args: |args| validate_optional_file(args, "ALVR dashboard binary"),
+            list_arg_choices: Vec::new,
+        });
+
+        registry.register(BackendRegistration {
+            name: "monado",
+            display_name: "Monado",
+            backend_type: BackendType::Monado,
+            args_kind: BackendArgsKind::Prefix,
+            args_required: false,
+            args_description: "Install prefix of a custom Monado build",
+            factory: |args| Ok(Box::new(MonadoBackend::new(MonadoBackend::locate_prefix(optional_path(args))?, Vec::new(), Vec::new())?)),
+            detect: || MonadoBackend::locate_prefix(None).map(|_| ()),
+            validate_args: |args| match args.is_empty() {
+                true => Ok(()),
+                false => MonadoBackend::locate_prefix(optional_path(args)).map(|_| ()),
+            },
+            list_arg_choices: Vec::new,
+        });
+
+        registry
+    }
+
+    pub fn register(&mut self, registration: BackendRegistration) {
+        self.backends.retain(|b| b.name != registration.name);
+        self.backends.push(registration);
+    }
+
+    pub fn find(&self, name: &str) -> anyhow::Result<&BackendRegistration> {
+        self.backends.iter()
+            .find(|b| b.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| anyhow::anyhow!("Unsupported VR backend: '{}'", name))
+    }
+
+    pub fn create(&self, name: &str, args: &str) -> anyhow::Result<(BackendType, Box<dyn VRBackend + Send + Sync>)> {
+        let registration = self.find(name)?;
+        Ok((registration.backend_type, (registration.factory)(args)?))
+    }
+
+    pub fn validate(&self, name: &str, args: &str) -> anyhow::Result<()> {
+        let registration = self.find(name)?;
+        if registration.args_required && args.is_empty() {
+            anyhow::bail!("The {} backend requires an argument: {}", registration.display_name, registration.args_description);
+        }
+
+        (registration.validate_args)(args)
+    }
+
+    pub fn list(&self) -> Vec<BackendInfo> {
+        self.backends.iter()
+            .map(|registration| {
+                let detection_result = (registration.detect)();
+                BackendInfo {
+                    name: registration.name.to_string(),
+                    display_name: registration.display_name.to_string(),
+                    args: BackendArgsSchema {
+                        kind: registration.args_kind,
+                        required: registration.args_required,
+                        description: registration.args_description.to_string(),
+                    },
+                    arg_choices: (registration.list_arg_choices)(),
+                    is_detected: detection_result.is_ok(),
+                    detection_error: detection_result.err().map(|e| e.to_string()),
+                }
+            })
+            .collect()
+    }
+}
+
+fn optional_path(args: &str) -> Option<PathBuf> {
+    match args.is_empty() {
+        true => None,
+        false => Some(PathBuf::from(args)),
+    }
+}
+
+fn validate_optional_file(args: &str, description: &str) -> anyhow::Result<()> {
+    if !args.is_empty() && !Path::new(args).is_file() {
+        anyhow::bail!("The {} '{}' does not exist", description, args);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_backend_is_an_error() {
+        let registry = BackendRegistry::new();
+
+        assert!(registry.find("WiVRn").is_ok());
+        assert!(registry.create("steamvr", "").is_err());
+        assert!(registry.validate("steamvr", "").is_err());
+    }
+
+    #[test]
+    fn test_required_args() {
+        let registry = BackendRegistry::new();
+
+        assert!(registry.validate("envision", "").is_err());
+        assert!(registry.validate("alvr", "/does/not/exist").is_err());
+        assert!(registry.validate("monado", "/does/not/exist").is_err());
+    }
+}
@@ -0,0 +1,169 @@
+use crate::adb::device_manager::DeviceManager;
+use crate::app_state::{AppState, AppStateWrapper};
+use crate::backends::VRBackend;
+use crate::events::ServerEvent;
+use crate::logging::log_channel::LogChannel;
+use crate::TokioMutex;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use ts_rs::TS;
+use uuid::Uuid;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+const READY_TIMEOUT: Duration = Duration::from_secs(10);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Restarts per game session, after which the backend is left down
+pub const MAX_RESTARTS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub enum BackendHealthState {
+    Healthy,
+    /// The backend has died and is about to be, or is being, restarted
+    Restarting,
+    /// Every restart has failed, the game has to be relaunched
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct BackendHealth {
+    pub state: BackendHealthState,
+    /// Restarts during the current game session
+    pub restart_count: u32,
+    pub error: Option<String>,
+}
+
+/// Watches the backend of the running game and restarts it with an increasing delay if its
+/// server process dies, up to [`MAX_RESTARTS`] times per session. Restarting the backend also
+/// reopens the ADB tunnel and the client app on the headset.
+pub fn spawn_backend_supervisor(app_state: AppStateWrapper) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut supervised_session = None;
+        let mut restart_count = 0;
+
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+
+            let (process_token, is_running) = {
+                let state = app_state.lock().await;
+                match (&state.active_game_session, &state.active_backend) {
+                    (Some(session), Some(backend)) => (*session.process_handle.get_process_token(), backend.is_running()),
+                    _ => continue,
+                }
+            };
+
+            if supervised_session != Some(process_token) {
+                supervised_session = Some(process_token);
+                restart_count = 0;
+            }
+            if is_running || restart_count >= MAX_RESTARTS {
+                continue;
+            }
+
+            restart_count += 1;
+            eprintln!("The VR backend has died, restarting it (attempt {}/{})", restart_count, MAX_RESTARTS);
+            set_health(&app_state, BackendHealthState::Restarting, restart_count, None).await;
+            tokio::time::sleep(backoff_delay(restart_count)).await;
+
+            match restart_backend(&app_state, &process_token, restart_count).await {
+                Ok(true) => {
+                    println!("The VR backend has been restarted");
+                    set_health(&app_state, BackendHealthState::Healthy, restart_count, None).await;
+                }
+                // The session has ended in the meantime
+                Ok(false) => {}
+                Err(error) => {
+                    eprintln!("Failed to restart the VR backend: {:?}", error);
+                    let state = match restart_count >= MAX_RESTARTS {
+                        true => BackendHealthState::Failed,
+                        // The next check notices that it is still down and tries again
+                        false => BackendHealthState::Restarting,
+                    };
+                    set_health(&app_state, state, restart_count, Some(error.to_string())).await;
+                }
+            }
+        }
+    })
+}
+
+/// Returns false if the session has ended before the backend could be restarted
+async fn restart_backend(app_state: &AppStateWrapper, process_token: &Uuid, restart_count: u32) -> anyhow::Result<bool> {
+    // The backend is taken out of the app state, so the lock is not held while it starts
+    let (mut backend, log_channel, device_manager) = {
+        let mut state = app_state.lock().await;
+        if !is_session_active(&state, process_token) {
+            return Ok(false);
+        }
+
+        // Taken by the shutdown before it stops the game
+        let Some(log_session) = state.log_session.as_mut() else {
+            return Ok(false);
+        };
+        let log_channel = log_session.get_or_create_channel(&format!("vr_backend_restart_{}", restart_count))?;
+        let Some(backend) = state.active_backend.take() else {
+            return Ok(false);
+        };
+        (backend, log_channel, state.device_manager.clone())
+    };
+
+    let result = start_backend(&mut backend, log_channel, device_manager).await;
+
+    let mut state = app_state.lock().await;
+    if !is_session_active(&state, process_token) {
+        // Nobody else would stop it, since it was not in the app state when the session ended
+        backend.stop()?;
+        return Ok(false);
+    }
+    state.active_backend = Some(backend);
+
+    result.map(|_| true)
+}
+
+async fn start_backend(backend: &mut Box<dyn VRBackend + Send + Sync>, log_channel: Arc<Mutex<LogChannel>>, device_manager: Arc<TokioMutex<DeviceManager>>) -> anyhow::Result<()> {
+    backend.start_async(log_channel, device_manager).await?;
+
+    let started_at = Instant::now();
+    while !backend.is_ready().await? {
+        anyhow::ensure!(started_at.elapsed() < READY_TIMEOUT, "The backend did not become ready after restarting");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+
+    Ok(())
+}
+
+fn is_session_active(state: &AppState, process_token: &Uuid) -> bool {
+    state.active_game_session.as_ref()
+        .is_some_and(|session| session.process_handle.get_process_token() == process_token)
+}
+
+async fn set_health(app_state: &AppStateWrapper, state: BackendHealthState, restart_count: u32, error: Option<String>) {
+    let health = BackendHealth { state, restart_count, error };
+    let mut app_state = app_state.lock().await;
+    _ = app_state.sock_tx.send(ServerEvent::BackendHealth(health.clone()));
+    app_state.backend_health = Some(health);
+}
+
+/// Doubles with every attempt, starting at [`INITIAL_BACKOFF`]
+fn backoff_delay(restart_count: u32) -> Duration {
+    INITIAL_BACKOFF.saturating_mul(1 << restart_count.saturating_sub(1).min(16)).min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(backoff_delay(1), Duration::from_secs(2));
+        assert_eq!(backoff_delay(2), Duration::from_secs(4));
+        assert_eq!(backoff_delay(3), Duration::from_secs(8));
+        assert_eq!(backoff_delay(10), MAX_BACKOFF);
+    }
+}
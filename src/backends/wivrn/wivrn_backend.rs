@@ -31,7 +31,7 @@ impl VRBackend for WiVRnBackend {
         let mut needs_new_server_process = false;
         let mut sys = System::new_all();
         sys.refresh_all();
-        if self.server_pid.as_mut().is_none_or(|pid| sys.process(Pid::from_u32(*pid)).unwrap().exists()) {
+        if self.server_pid.is_none_or(|pid| sys.process(Pid::from_u32(pid)).is_none()) {
             needs_new_server_process = true;
 
             // Kill any existing processes
@@ -95,11 +95,11 @@ impl VRBackend for WiVRnBackend {
         self.reconnect_async(device_manager.clone()).await?;
 
         let device_manager = device_manager.lock().await;
-        let active_device = device_manager.get_current_device_async().await?
-            .ok_or_else(|| anyhow::anyhow!("No active device found"))?;
+        let active_device = device_manager.get_selected_device_async().await?
+            .ok_or_else(|| anyhow::anyhow!("No VR device is selected"))?;
 
         Ok(BackendStartInfo {
-            vr_device_serial: active_device.usb_serial,
+            vr_device_serial: Some(active_device.usb_serial),
             vr_device_ip: active_device.ip_address,
             was_restarted: needs_new_server_process,
         })
@@ -144,6 +144,10 @@ impl VRBackend for WiVRnBackend {
 
         Ok(())
     }
+
+    fn is_running(&self) -> bool {
+        self.server_wait_handle.as_ref().is_none_or(|handle| !handle.is_finished())
+    }
 }
 
 impl WiVRnBackend {
@@ -160,8 +164,8 @@ impl WiVRnBackend {
         // Forward socket connection
         println!("Forwarding socket connection...");
         let device_manager = device_manager.lock().await;
-        let active_device = device_manager.get_current_device_async().await?
-            .ok_or_else(|| anyhow::anyhow!("No active device found"))?;
+        let active_device = device_manager.get_selected_device_async().await?
+            .ok_or_else(|| anyhow::anyhow!("No VR device is selected"))?;
         active_device.try_open_tcp_tunnel(9757)?;
 
         // Start the WiVRn client
@@ -204,7 +208,7 @@ impl WiVRnBackend {
         bail!("WiVRn manifest file not found")
     }
 
-    fn locate_server_binary_path(server_binary: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+    pub fn locate_server_binary_path(server_binary: Option<PathBuf>) -> anyhow::Result<PathBuf> {
         // Check if the path has been explicitly specified
         if let Some(server_binary) = server_binary {
             if server_binary.exists() && server_binary.is_file() {
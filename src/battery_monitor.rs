@@ -1,4 +1,5 @@
 use crate::adb::device_manager::DeviceManager;
+use crate::events::ServerEvent;
 use futures_util::FutureExt;
 use num_enum::FromPrimitive;
 use serde::Serialize;
@@ -24,7 +25,7 @@ pub struct BatteryMonitor {
 }
 
 impl BatteryMonitor {
-    pub fn new(ws_tx: Sender<String>, device_manager: Arc<Mutex<DeviceManager>>, stop_ch: Sender<()>) -> Self {
+    pub fn new(ws_tx: Sender<ServerEvent>, device_manager: Arc<Mutex<DeviceManager>>, stop_ch: Sender<()>) -> Self {
         let mut stop_rx = stop_ch.subscribe();
         let is_active = Arc::new(AtomicBool::new(true));
         let active_serial = Arc::new(Mutex::new(None));
@@ -55,7 +56,16 @@ impl BatteryMonitor {
                     //println!("Will try to query battery levels");
 
                     let device_manager = device_manager.lock().await;
-                    if let Ok(Some(current_device)) = device_manager.get_current_device_async().await {
+                    if let Ok(Some(current_device)) = device_manager.get_selected_device_async().await {
+                        // The history belongs to a single headset, start over when another one is selected
+                        let mut active_serial = active_serial.lock().await;
+                        if active_serial.as_ref() != Some(&current_device.usb_serial) {
+                            previous_percentage.lock().await.clear();
+                            *current_info.lock().await = None;
+                            active_serial.replace(current_device.usb_serial.clone());
+                        }
+                        drop(active_serial);
+
                         if let Ok(battery_output) = current_device.adb_shell_command(&["dumpsys", "battery"]) {
                             let dumpsys = String::from_utf8_lossy(&battery_output.stdout).to_string();
                             let power_info = AndroidBatteryStats::try_parse(&dumpsys).unwrap();
@@ -72,7 +82,7 @@ impl BatteryMonitor {
                                 history: percentage_history.clone(),
                             };
 
-                            _ = ws_tx.send(format!("battery:{}", serde_json::to_string(&battery_info).unwrap()));
+                            _ = ws_tx.send(ServerEvent::Battery(battery_info.clone()));
 
                             *current_info.lock().await = Some(battery_info);
                         } else {
@@ -70,7 +70,7 @@ pub fn parse_linux_command(command: &str) -> Result<ParsedCommand, String> {
 }
 
 /// Tokenizes a command string, respecting quotes and escapes
-fn tokenize_command(command: &str) -> Result<Vec<String>, String> {
+pub(crate) fn tokenize_command(command: &str) -> Result<Vec<String>, String> {
     let mut tokens = Vec::new();
     let mut current_token = String::new();
     let mut chars = command.chars().peekable();
@@ -131,6 +131,64 @@ fn tokenize_command(command: &str) -> Result<Vec<String>, String> {
     Ok(tokens)
 }
 
+/// Splits the `arguments` of a Steam launch config the way Windows' `CommandLineToArgvW` does,
+/// since most launch configs are written for Windows games. Unlike [`tokenize_command`],
+/// backslashes are only special in front of a double quote, so Windows paths are kept intact.
+/// Single quotes are ordinary characters and an unclosed double quote runs to the end of the string.
+pub fn tokenize_steam_arguments(arguments: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current_token = String::new();
+    // Set by quotes too, so `""` is passed on as an empty argument
+    let mut has_token = false;
+    let mut in_quotes = false;
+    let mut chars = arguments.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => {
+                let mut backslashes = 1;
+                while chars.next_if_eq(&'\\').is_some() {
+                    backslashes += 1;
+                }
+
+                match chars.peek() {
+                    // 2n backslashes and a quote are n backslashes and a delimiter,
+                    // 2n + 1 backslashes and a quote are n backslashes and a literal quote
+                    Some('"') => {
+                        current_token.extend(std::iter::repeat_n('\\', backslashes / 2));
+                        if backslashes % 2 == 1 {
+                            current_token.push('"');
+                            chars.next();
+                        }
+                    }
+                    _ => current_token.extend(std::iter::repeat_n('\\', backslashes)),
+                }
+                has_token = true;
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            ' ' | '\t' if !in_quotes => {
+                if has_token {
+                    tokens.push(std::mem::take(&mut current_token));
+                    has_token = false;
+                }
+            }
+            _ => {
+                current_token.push(ch);
+                has_token = true;
+            }
+        }
+    }
+
+    if has_token {
+        tokens.push(current_token);
+    }
+
+    tokens
+}
+
 /// Determines the working directory based on the rules provided
 fn determine_working_directory(env_vars: &HashMap<String, String>, executable: &str) -> Result<String, String> {
     // First priority: CWD environment variable
@@ -228,4 +286,13 @@ mod tests {
         assert_eq!(parsed.arguments[0], "line1\nline2");
         assert_eq!(parsed.arguments[1], "quote\"inside");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_steam_arguments() {
+        assert_eq!(tokenize_steam_arguments("-vr  -screen-fullscreen 0 "), vec!["-vr", "-screen-fullscreen", "0"]);
+        assert_eq!(tokenize_steam_arguments(r#"+map "de dust" -name 'x'"#), vec!["+map", "de dust", "-name", "'x'"]);
+        assert_eq!(tokenize_steam_arguments(r#"-path C:\Games\Data -dir "C:\My Game\\" -q \"hi\""#), vec!["-path", r"C:\Games\Data", "-dir", r"C:\My Game\", "-q", "\"hi\""]);
+        assert_eq!(tokenize_steam_arguments(r#"-title "" -open "unclosed arg"#), vec!["-title", "", "-open", "unclosed arg"]);
+        assert!(tokenize_steam_arguments("   ").is_empty());
+    }
+}
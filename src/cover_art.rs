@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use image::codecs::jpeg::JpegEncoder;
+use image::ImageFormat;
+use lazy_static::lazy_static;
+
+const THUMBNAIL_WIDTH: u32 = 300;
+const THUMBNAIL_HEIGHT: u32 = 450;
+const THUMBNAIL_QUALITY: u8 = 85;
+
+lazy_static! {
+    static ref THUMBNAIL_CACHE: Mutex<HashMap<String, Arc<Vec<u8>>>> = Mutex::new(HashMap::new());
+}
+
+pub fn detect_image_format(data: &[u8]) -> Option<ImageFormat> {
+    match image::guess_format(data) {
+        Ok(format @ (ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP)) => Some(format),
+        _ => None,
+    }
+}
+
+pub fn mime_type_of(data: &[u8]) -> &'static str {
+    match detect_image_format(data) {
+        Some(format) => format.to_mime_type(),
+        None => "application/octet-stream",
+    }
+}
+
+/// Returns a small JPEG version of the cover, generated on first use and kept in memory
+pub fn get_thumbnail(game_id: &str, cover: &[u8]) -> anyhow::Result<Arc<Vec<u8>>> {
+    if let Some(thumbnail) = THUMBNAIL_CACHE.lock().unwrap().get(game_id) {
+        return Ok(thumbnail.clone());
+    }
+
+    let thumbnail = Arc::new(create_thumbnail(cover)?);
+    THUMBNAIL_CACHE.lock().unwrap()
+        .insert(game_id.to_string(), thumbnail.clone());
+
+    Ok(thumbnail)
+}
+
+pub fn invalidate_thumbnail(game_id: &str) {
+    THUMBNAIL_CACHE.lock().unwrap().remove(game_id);
+}
+
+fn create_thumbnail(cover: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let image = image::load_from_memory(cover)?
+        .thumbnail(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT)
+        .into_rgb8();
+
+    let mut output = Cursor::new(Vec::new());
+    image.write_with_encoder(JpegEncoder::new_with_quality(&mut output, THUMBNAIL_QUALITY))?;
+
+    Ok(output.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    fn encode(format: ImageFormat, width: u32, height: u32) -> Vec<u8> {
+        let image = RgbaImage::from_pixel(width, height, Rgba([200, 100, 50, 255]));
+        let mut output = Cursor::new(Vec::new());
+        image.write_to(&mut output, format).unwrap();
+        output.into_inner()
+    }
+
+    #[test]
+    fn test_detects_mime_types() {
+        assert_eq!(mime_type_of(&encode(ImageFormat::Png, 4, 4)), "image/png");
+        assert_eq!(mime_type_of(&encode(ImageFormat::WebP, 4, 4)), "image/webp");
+        assert_eq!(mime_type_of(b"not an image"), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_thumbnail_fits_bounds() {
+        let thumbnail = create_thumbnail(&encode(ImageFormat::Png, 600, 900)).unwrap();
+        let decoded = image::load_from_memory(&thumbnail).unwrap();
+
+        assert_eq!(mime_type_of(&thumbnail), "image/jpeg");
+        assert_eq!((decoded.width(), decoded.height()), (THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT));
+    }
+}
@@ -0,0 +1,73 @@
+use std::env;
+use std::path::PathBuf;
+use anyhow::Context;
+use diesel::connection::SimpleConnection;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool};
+use diesel::SqliteConnection;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use dotenvy::dotenv;
+
+pub type DbPool = Pool<ConnectionManager<SqliteConnection>>;
+
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+const DATABASE_FILE_NAME: &str = "launcher.db";
+
+#[derive(Debug)]
+struct ConnectionOptions;
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for ConnectionOptions {
+    fn on_acquire(&self, connection: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        connection.batch_execute("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000; PRAGMA journal_mode = WAL;")
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+/// Opens the connection pool and brings the schema up to date before anything else touches it
+pub fn create_pool() -> anyhow::Result<DbPool> {
+    let database_url = resolve_database_url()?;
+    println!("Using database at {}", database_url);
+
+    let pool = Pool::builder()
+        .connection_customizer(Box::new(ConnectionOptions))
+        .build(ConnectionManager::<SqliteConnection>::new(&database_url))
+        .with_context(|| format!("Failed to open the database at {}", database_url))?;
+
+    let mut connection = pool.get()?;
+    let applied = connection.run_pending_migrations(MIGRATIONS)
+        .map_err(|e| anyhow::anyhow!("Failed to run database migrations: {}", e))?;
+    for migration in applied {
+        println!("Applied database migration {}", migration);
+    }
+
+    Ok(pool)
+}
+
+/// A migrated in-memory database, for tests that touch the schema
+#[cfg(test)]
+pub fn open_test_connection() -> SqliteConnection {
+    use diesel::Connection;
+
+    let mut connection = SqliteConnection::establish(":memory:").unwrap();
+    connection.run_pending_migrations(MIGRATIONS).unwrap();
+    connection
+}
+
+/// Uses `DATABASE_URL` when it is set, otherwise `$XDG_DATA_HOME/vr-launcher/launcher.db`
+fn resolve_database_url() -> anyhow::Result<String> {
+    dotenv().ok();
+
+    if let Ok(database_url) = env::var("DATABASE_URL") {
+        return Ok(database_url);
+    }
+
+    let data_dir = env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::home_dir().map(|home| home.join(".local/share")))
+        .ok_or_else(|| anyhow::anyhow!("Could not determine the data directory, please set DATABASE_URL"))?
+        .join("vr-launcher");
+    std::fs::create_dir_all(&data_dir)?;
+
+    Ok(data_dir.join(DATABASE_FILE_NAME).to_string_lossy().to_string())
+}
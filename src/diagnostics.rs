@@ -0,0 +1,295 @@
+use crate::models::Game;
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::File;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::time::SystemTime;
+use sysinfo::System;
+use tar::{Builder, Header};
+use tokio::process;
+use ts_rs::TS;
+
+/// A game that exits on its own sooner than this is treated as a crash, even with exit code 0
+pub const EARLY_EXIT_SECS: u64 = 10;
+/// Variables whose names contain one of these are left out of the bundle, it is served on the LAN
+const SECRET_MARKERS: [&str; 5] = ["TOKEN", "SECRET", "KEY", "PASSWORD", "AUTH"];
+const REDACTED: &str = "<redacted>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub enum CrashKind {
+    NonZeroExit,
+    Signal,
+    EarlyExit,
+}
+
+impl CrashKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CrashKind::NonZeroExit => "nonZeroExit",
+            CrashKind::Signal => "signal",
+            CrashKind::EarlyExit => "earlyExit",
+        }
+    }
+}
+
+/// Sent over the socket when a game has exited abnormally, see [`classify_exit`]
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct CrashInfo {
+    pub game_id: String,
+    pub kind: CrashKind,
+    pub exit_code: Option<i32>,
+    pub exit_signal: Option<i32>,
+    pub run_time_secs: u64,
+    /// The file name for `/api/diagnostics/{file_name}`, unless the bundle could not be created
+    pub diagnostics_bundle: Option<String>,
+}
+
+/// Returns why the exit of a game that was not stopped by the launcher counts as a crash, if it does
+pub fn classify_exit(exit_status: Option<ExitStatus>, run_time_secs: u64) -> Option<CrashKind> {
+    match exit_status {
+        Some(status) if status.signal().is_some() => Some(CrashKind::Signal),
+        Some(status) if status.code() != Some(0) => Some(CrashKind::NonZeroExit),
+        _ if run_time_secs < EARLY_EXIT_SECS => Some(CrashKind::EarlyExit),
+        _ => None,
+    }
+}
+
+/// The command line and the full environment a game was started with
+#[derive(Debug, Clone, Default)]
+pub struct LaunchEnvironment {
+    pub command_line: Vec<String>,
+    pub env: BTreeMap<String, String>,
+}
+
+impl LaunchEnvironment {
+    /// The launcher's own environment, with the changes the launch modifiers made to `command`.
+    /// The values of variables that look like credentials are redacted.
+    pub fn capture(command: &process::Command) -> Self {
+        let command = command.as_std();
+        let mut env = env::vars_os()
+            .map(|(key, value)| (key.to_string_lossy().into_owned(), value.to_string_lossy().into_owned()))
+            .collect::<BTreeMap<_, _>>();
+        for (key, value) in command.get_envs() {
+            let key = key.to_string_lossy().into_owned();
+            match value {
+                Some(value) => env.insert(key, value.to_string_lossy().into_owned()),
+                None => env.remove(&key),
+            };
+        }
+        for (key, value) in env.iter_mut() {
+            if is_secret(key) {
+                *value = REDACTED.to_string();
+            }
+        }
+
+        Self {
+            command_line: std::iter::once(command.get_program())
+                .chain(command.get_args())
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            env,
+        }
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!("$ {}\n\n", self.command_line.join(" "));
+        for (key, value) in &self.env {
+            text.push_str(&format!("{}={}\n", key, value));
+        }
+
+        text
+    }
+}
+
+fn is_secret(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    SECRET_MARKERS.iter().any(|marker| key.contains(marker))
+}
+
+/// Everything but the logs and the environment, written to `diagnostics.json` in the bundle
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsReport<'a> {
+    pub crash: &'a CrashInfo,
+    pub game: &'a Game,
+    pub proton_version: Option<&'a str>,
+    pub vr_backend: &'a str,
+    pub vr_backend_args: &'a str,
+    pub vr_device_serial: Option<&'a str>,
+    pub system: SystemReport,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemReport {
+    os: Option<String>,
+    kernel_version: Option<String>,
+    cpu: Option<String>,
+    cpu_count: usize,
+    total_memory_bytes: u64,
+    available_memory_bytes: u64,
+    uptime_secs: u64,
+    launcher_version: &'static str,
+}
+
+impl SystemReport {
+    pub fn collect() -> Self {
+        let mut sys = System::new();
+        sys.refresh_memory();
+        sys.refresh_cpu_list(sysinfo::CpuRefreshKind::nothing());
+
+        Self {
+            os: System::long_os_version(),
+            kernel_version: System::kernel_version(),
+            cpu: sys.cpus().first().map(|cpu| cpu.brand().to_string()),
+            cpu_count: sys.cpus().len(),
+            total_memory_bytes: sys.total_memory(),
+            available_memory_bytes: sys.available_memory(),
+            uptime_secs: System::uptime(),
+            launcher_version: env!("CARGO_PKG_VERSION"),
+        }
+    }
+}
+
+/// Where diagnostics bundles are kept, next to the `logs` folder
+pub fn diagnostics_dir() -> anyhow::Result<PathBuf> {
+    Ok(env::current_exe()?
+        .parent().unwrap()
+        .join("diagnostics"))
+}
+
+/// Packs the report, the launch environment and the log files into a `.tar.gz` in `output_dir`
+/// and returns its file name
+pub fn write_bundle(output_dir: &Path, report: &DiagnosticsReport, environment: &LaunchEnvironment, log_files: &[PathBuf]) -> anyhow::Result<String> {
+    std::fs::create_dir_all(output_dir)?;
+
+    let datetime: DateTime<Utc> = SystemTime::now().into();
+    let file_name = format!("{}_{}_crash.tar.gz", datetime.format("%Y-%m-%d_%H-%M-%S"), report.game.id);
+    let encoder = GzEncoder::new(File::create(output_dir.join(&file_name))?, Compression::default());
+    let mut tar = Builder::new(encoder);
+
+    append_text(&mut tar, "diagnostics.json", &serde_json::to_string_pretty(report)?)?;
+    append_text(&mut tar, "environment.txt", &environment.to_text())?;
+    for log_file in log_files.iter().filter(|path| path.exists()) {
+        tar.append_path_with_name(log_file, Path::new("logs").join(log_file.file_name().unwrap()))?;
+    }
+
+    tar.into_inner()?.finish()?;
+
+    Ok(file_name)
+}
+
+fn append_text(tar: &mut Builder<GzEncoder<File>>, name: &str, text: &str) -> anyhow::Result<()> {
+    let mut header = Header::new_gnu();
+    header.set_size(text.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs());
+    header.set_cksum();
+    tar.append_data(&mut header, name, text.as_bytes())?;
+
+    Ok(())
+}
+
+/// Bundles are only served by file name, never by path
+pub fn is_valid_bundle_name(file_name: &str) -> bool {
+    file_name.ends_with(".tar.gz")
+        && !file_name.starts_with('.')
+        && !file_name.contains(['/', '\\'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use tar::Archive;
+
+    #[test]
+    fn test_classify_exit() {
+        let exited = |code: i32| Some(ExitStatus::from_raw(code << 8));
+        let signalled = |signal: i32| Some(ExitStatus::from_raw(signal));
+
+        assert_eq!(classify_exit(exited(0), 3600), None);
+        assert_eq!(classify_exit(exited(0), 2), Some(CrashKind::EarlyExit));
+        assert_eq!(classify_exit(exited(1), 3600), Some(CrashKind::NonZeroExit));
+        assert_eq!(classify_exit(signalled(11), 3600), Some(CrashKind::Signal));
+        assert_eq!(classify_exit(None, 3600), None);
+    }
+
+    #[test]
+    fn test_capture_redacts_secrets() {
+        let mut command = process::Command::new("/usr/bin/true");
+        command.env("SVRL_TOKEN", "token");
+        command.env("STEAM_API_KEY", "key");
+        command.env("PROTON_LOG", "1");
+
+        let environment = LaunchEnvironment::capture(&command);
+        assert_eq!(environment.env["SVRL_TOKEN"], REDACTED);
+        assert_eq!(environment.env["STEAM_API_KEY"], REDACTED);
+        assert_eq!(environment.env["PROTON_LOG"], "1");
+    }
+
+    #[test]
+    fn test_bundle_contents() {
+        let output_dir = env::temp_dir().join(format!("svrl-diagnostics-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&output_dir).unwrap();
+        let log_file = output_dir.join("game.log");
+        std::fs::write(&log_file, "fatal error\n").unwrap();
+
+        let game = Game {
+            id: "game".into(),
+            title: "Test Game".into(),
+            cover: None,
+            vr_backend: "wivrn".into(),
+            vr_backend_args: String::new(),
+            pressure_vessel: false,
+            steam_app_id: None,
+            proton_version: None,
+            command_line: Some("/usr/bin/true".into()),
+            use_overlay: false,
+            use_systemd_scope: false,
+            prefix_id: None,
+            winetricks_verbs: String::new(),
+            launch_options: String::new(),
+            steam_launch_config: None,
+        };
+        let crash = CrashInfo {
+            game_id: game.id.clone(),
+            kind: CrashKind::EarlyExit,
+            exit_code: Some(0),
+            exit_signal: None,
+            run_time_secs: 1,
+            diagnostics_bundle: None,
+        };
+        let report = DiagnosticsReport {
+            crash: &crash,
+            game: &game,
+            proton_version: None,
+            vr_backend: "wivrn",
+            vr_backend_args: "",
+            vr_device_serial: Some("serial"),
+            system: SystemReport::collect(),
+        };
+
+        let file_name = write_bundle(&output_dir, &report, &LaunchEnvironment::default(), &[log_file]).unwrap();
+        assert!(is_valid_bundle_name(&file_name));
+
+        let mut archive = Archive::new(GzDecoder::new(File::open(output_dir.join(&file_name)).unwrap()));
+        let mut entries = archive.entries().unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        entries.sort();
+        assert_eq!(entries, ["diagnostics.json", "environment.txt", "logs/game.log"]);
+
+        std::fs::remove_dir_all(output_dir).unwrap();
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::adb::device_manager::VrDeviceInfo;
+use crate::audio_api::AudioDevice;
+use crate::backends::supervisor::BackendHealth;
+use crate::battery_monitor::AndroidBatteryInfo;
+use crate::diagnostics::CrashInfo;
+use crate::launch::LaunchProgress;
+use crate::models::Game;
+use crate::routes::audio::AudioEndpoint;
+
+/// Bumped whenever a change to `ServerEvent` would break existing clients
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Every message the server pushes over `/api/sock`, serialized as `{ "type": ..., "data": ... }`
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(tag = "type", content = "data", rename_all = "camelCase")]
+pub enum ServerEvent {
+    /// Always the first message on a new connection
+    Hello(HelloInfo),
+    /// The full current state, sent right after `Hello`
+    Snapshot(Box<StateSnapshot>),
+    Battery(AndroidBatteryInfo),
+    Active(Box<GameSessionInfo>),
+    Inactive,
+    /// Sent on every step of a launch, see `LaunchState`
+    LaunchProgress(LaunchProgress),
+    DefaultInputChanged(AudioDevice),
+    DefaultOutputChanged(AudioDevice),
+    VolumeMuteChanged(AudioDevice),
+    LibraryChanged,
+    /// Sent whenever the supervisor notices the backend has died, or has restarted it
+    BackendHealth(BackendHealth),
+    /// A game has exited abnormally without being stopped by the launcher
+    GameCrashed(CrashInfo),
+    /// Sent only to the client that issued the command, once it has succeeded
+    CommandAck(CommandAck),
+    /// Sent only to the client that issued the command, when it has failed or could not be parsed
+    CommandError(CommandError),
+}
+
+/// A command sent by a client over `/api/sock`; the reply carries the same `request_id`
+#[derive(Debug, Deserialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct ClientMessage {
+    pub request_id: String,
+    pub command: ClientCommand,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(tag = "type", content = "data", rename_all = "camelCase")]
+pub enum ClientCommand {
+    #[serde(rename_all = "camelCase")]
+    LaunchGame {
+        game_id: String,
+        /// Wait for the current session to end instead of failing while a game is running
+        #[serde(default)]
+        queue: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    SwitchGame { game_id: String },
+    #[serde(rename_all = "camelCase")]
+    CancelLaunch { launch_id: String },
+    KillGame,
+    #[serde(rename_all = "camelCase")]
+    SetVolume { device_id: u32, volume: u8, muted: bool },
+    #[serde(rename_all = "camelCase")]
+    SetDefaultDevice { endpoint: AudioEndpoint, device_id: u32 },
+    ReloadBackend,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct CommandAck {
+    pub request_id: String,
+    /// Set for `LaunchGame` and `SwitchGame`, for matching `LaunchProgress` events and cancelling the launch
+    pub launch_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct CommandError {
+    pub request_id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct HelloInfo {
+    pub protocol_version: u32,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct GameSessionInfo {
+    pub game: Game,
+    pub start_time_epoch: u64,
+    pub vr_device_serial: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct StateSnapshot {
+    pub active_session: Option<GameSessionInfo>,
+    pub launch: Option<LaunchProgress>,
+    pub launch_queue: Vec<LaunchProgress>,
+    pub backend_health: Option<BackendHealth>,
+    pub battery: Option<AndroidBatteryInfo>,
+    pub default_input: Option<AudioDevice>,
+    pub default_output: Option<AudioDevice>,
+    pub devices: Vec<VrDeviceInfo>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_format() {
+        let hello = ServerEvent::Hello(HelloInfo { protocol_version: PROTOCOL_VERSION });
+        assert_eq!(serde_json::to_string(&hello).unwrap(), r#"{"type":"hello","data":{"protocolVersion":1}}"#);
+        assert_eq!(serde_json::to_string(&ServerEvent::Inactive).unwrap(), r#"{"type":"inactive"}"#);
+    }
+
+    #[test]
+    fn test_client_message_format() {
+        let message: ClientMessage = serde_json::from_str(
+            r#"{"requestId":"42","command":{"type":"setDefaultDevice","data":{"endpoint":"outputs","deviceId":7}}}"#).unwrap();
+        assert_eq!(message.request_id, "42");
+        assert!(matches!(message.command, ClientCommand::SetDefaultDevice { endpoint: AudioEndpoint::Outputs, device_id: 7 }));
+
+        let message: ClientMessage = serde_json::from_str(r#"{"requestId":"43","command":{"type":"killGame"}}"#).unwrap();
+        assert!(matches!(message.command, ClientCommand::KillGame));
+    }
+}
@@ -0,0 +1,123 @@
+use crate::models::LaunchRequest;
+use crate::schema::launch_requests::dsl::*;
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, QueryResult, RunQueryDsl, SelectableHelper, SqliteConnection};
+use serde::Serialize;
+use ts_rs::TS;
+
+/// How long a retried launch request is answered with the original launch's result
+pub const TOKEN_TTL_SECS: i64 = 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub enum LaunchOutcome {
+    Started,
+    Failed,
+    Cancelled,
+    /// Never recorded, the launcher was restarted before the launch had an outcome
+    Interrupted,
+}
+
+/// The answer to a retried launch request whose launch has already ended
+#[derive(Debug, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchReplay {
+    pub launch_id: String,
+    pub outcome: LaunchOutcome,
+    pub error: Option<String>,
+}
+
+impl LaunchOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LaunchOutcome::Started => "started",
+            LaunchOutcome::Failed => "failed",
+            LaunchOutcome::Cancelled => "cancelled",
+            LaunchOutcome::Interrupted => "interrupted",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "started" => Some(LaunchOutcome::Started),
+            "failed" => Some(LaunchOutcome::Failed),
+            "cancelled" => Some(LaunchOutcome::Cancelled),
+            "interrupted" => Some(LaunchOutcome::Interrupted),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the launch that was started with `request_token`, unless it has expired
+pub fn find_request(connection: &mut SqliteConnection, request_token: &str, now: i64) -> QueryResult<Option<LaunchRequest>> {
+    launch_requests
+        .select(LaunchRequest::as_select())
+        .find(request_token)
+        .filter(created_at.gt(now - TOKEN_TTL_SECS))
+        .first(connection)
+        .optional()
+}
+
+pub fn record_request(connection: &mut SqliteConnection, request_token: &str, request_launch_id: &str, request_game_id: &str, now: i64) -> QueryResult<()> {
+    purge_expired(connection, now)?;
+
+    // An expired token may still be in the table, it is simply reused
+    diesel::replace_into(launch_requests)
+        .values(LaunchRequest {
+            token: request_token.to_string(),
+            launch_id: request_launch_id.to_string(),
+            game_id: request_game_id.to_string(),
+            outcome: None,
+            error: None,
+            created_at: now,
+        })
+        .execute(connection)?;
+
+    Ok(())
+}
+
+pub fn record_outcome(connection: &mut SqliteConnection, request_launch_id: &str, launch_outcome: LaunchOutcome, launch_error: Option<&str>) -> QueryResult<()> {
+    diesel::update(launch_requests.filter(launch_id.eq(request_launch_id)))
+        .set((outcome.eq(launch_outcome.as_str()), error.eq(launch_error)))
+        .execute(connection)?;
+
+    Ok(())
+}
+
+fn purge_expired(connection: &mut SqliteConnection, now: i64) -> QueryResult<usize> {
+    diesel::delete(launch_requests.filter(created_at.le(now - TOKEN_TTL_SECS)))
+        .execute(connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::open_test_connection;
+
+    #[test]
+    fn test_request_outcome_is_remembered() {
+        let connection = &mut open_test_connection();
+        record_request(connection, "token", "launch", "game", 1000).unwrap();
+
+        let request = find_request(connection, "token", 1000).unwrap().unwrap();
+        assert_eq!(request.launch_id, "launch");
+        assert_eq!(request.outcome, None);
+
+        record_outcome(connection, "launch", LaunchOutcome::Failed, Some("No VR device is selected")).unwrap();
+        let request = find_request(connection, "token", 1001).unwrap().unwrap();
+        assert_eq!(request.outcome.as_deref().and_then(LaunchOutcome::parse), Some(LaunchOutcome::Failed));
+        assert_eq!(request.error.as_deref(), Some("No VR device is selected"));
+    }
+
+    #[test]
+    fn test_expired_tokens_are_forgotten() {
+        let connection = &mut open_test_connection();
+        record_request(connection, "old", "launch-1", "game", 1000).unwrap();
+
+        assert!(find_request(connection, "old", 1000 + TOKEN_TTL_SECS).unwrap().is_none());
+
+        record_request(connection, "new", "launch-2", "game", 1000 + TOKEN_TTL_SECS).unwrap();
+        assert_eq!(launch_requests.count().get_result::<i64>(connection).unwrap(), 1);
+    }
+}
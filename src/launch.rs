@@ -0,0 +1,313 @@
+use crate::app_state::{AppState, AppStateWrapper};
+use crate::backends::VRBackend;
+use crate::events::ServerEvent;
+use crate::models::Game;
+use crate::steam::launch_modifiers::LaunchModifier;
+use crate::steam::launch_options::LaunchOptions;
+use crate::steam::steam_interface::{ProtonLaunchInfo, ProtonVersion, SteamApp};
+use crate::steam::winetricks;
+use anyhow::ensure;
+use futures_util::FutureExt;
+use serde::Serialize;
+use std::fmt;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, MutexGuard};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use ts_rs::TS;
+
+const BACKEND_READY_TIMEOUT: Duration = Duration::from_secs(5);
+const AUDIO_DEVICE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub enum LaunchState {
+    /// Waiting for the current session to end, see `AppState::queue_launch`
+    Queued,
+    Preparing,
+    /// Installing the game's missing winetricks verbs into its Proton prefix
+    InstallingVerbs,
+    StartingBackend,
+    WaitingForHeadset,
+    ConfiguringAudio,
+    StartingOverlay,
+    Running,
+    Stopping,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchProgress {
+    pub launch_id: String,
+    pub game_id: String,
+    pub state: LaunchState,
+    /// Only set when the launch has failed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchStarted {
+    pub launch_id: String,
+}
+
+/// How a killed game has exited, `None` if it is unknown
+#[derive(Debug, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct GameExitInfo {
+    pub exit_code: Option<i32>,
+    pub exit_signal: Option<i32>,
+}
+
+/// The launch that is currently in progress, or whose game is running
+pub struct ActiveLaunch {
+    pub progress: LaunchProgress,
+    /// Checked by the launch task on every step boundary
+    pub cancel_requested: Arc<AtomicBool>,
+}
+
+impl ActiveLaunch {
+    pub fn new(progress: LaunchProgress) -> Self {
+        Self {
+            progress,
+            cancel_requested: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LaunchCancelled;
+
+impl fmt::Display for LaunchCancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The launch was cancelled")
+    }
+}
+
+impl std::error::Error for LaunchCancelled {}
+
+/// Starts the next queued launch whenever a session ends or a launch did not get to run
+pub fn spawn_queue_runner(app_state: AppStateWrapper, mut events: broadcast::Receiver<ServerEvent>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(ServerEvent::Inactive) => {}
+                Ok(ServerEvent::LaunchProgress(progress)) if matches!(progress.state, LaunchState::Failed | LaunchState::Cancelled) => {}
+                Ok(_) => continue,
+                // Missed events may have included the end of a session
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+
+            app_state.lock().await.start_next_queued_launch(app_state.clone());
+        }
+    })
+}
+
+/// Everything the `Preparing` step has resolved for the later steps
+pub struct PreparedLaunch {
+    pub steam_app: SteamApp,
+    pub compat_info: Option<ProtonLaunchInfo>,
+    pub prefix_id: String,
+    pub launch_options: LaunchOptions,
+    pub modifiers: Vec<Box<dyn LaunchModifier>>,
+    pub backend: Box<dyn VRBackend + Send + Sync>,
+}
+
+/// Runs the steps of a single launch in the background. The app state is only locked
+/// for the duration of each step, so the launcher stays responsive while e.g. the
+/// backend is waiting for the headset.
+pub struct LaunchTask {
+    app_state: AppStateWrapper,
+    launch_id: String,
+    game: Game,
+    cancel_requested: Arc<AtomicBool>,
+}
+
+impl LaunchTask {
+    pub fn new(app_state: AppStateWrapper, launch_id: String, game: Game, cancel_requested: Arc<AtomicBool>) -> Self {
+        Self { app_state, launch_id, game, cancel_requested }
+    }
+
+    pub async fn run(self) {
+        // Owned by the task until the game is running, so a failed launch can stop it
+        let mut backend = None;
+        // A panicking step must not leave `active_launch` set, that would block every later launch
+        let result = AssertUnwindSafe(self.run_steps(&mut backend)).catch_unwind().await
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("The launch has failed unexpectedly")));
+        let Err(error) = result else {
+            return;
+        };
+
+        let mut app_state = self.app_state.lock().await;
+        if let Err(cleanup_error) = Self::clean_up(&mut app_state, backend) {
+            eprintln!("Failed to clean up after launch {}: {:?}", self.launch_id, cleanup_error);
+        }
+
+        match error.is::<LaunchCancelled>() {
+            true => app_state.set_launch_state(&self.launch_id, LaunchState::Cancelled, None),
+            false => {
+                eprintln!("Failed to launch game {}: {:?}", self.game.id, error);
+                app_state.set_launch_state(&self.launch_id, LaunchState::Failed, Some(error.to_string()));
+            }
+        }
+        app_state.active_launch = None;
+    }
+
+    /// Stops whatever this launch has started and archives its partial logs
+    fn clean_up(app_state: &mut AppState, backend: Option<Box<dyn VRBackend + Send + Sync>>) -> anyhow::Result<()> {
+        if let Some(mut backend) = backend {
+            backend.stop()?;
+        }
+        app_state.overlay_manager.stop()?;
+        if let Some(mut log_session) = app_state.log_session.take() {
+            log_session.shutdown()?;
+        }
+
+        Ok(())
+    }
+
+    /// Locks the app state for the next step, or bails if the launch was cancelled in the meantime
+    async fn enter_state(&self, state: LaunchState) -> anyhow::Result<MutexGuard<'_, AppState>> {
+        let mut app_state = self.app_state.lock().await;
+        self.check_cancelled()?;
+        app_state.set_launch_state(&self.launch_id, state, None);
+
+        Ok(app_state)
+    }
+
+    fn check_cancelled(&self) -> anyhow::Result<()> {
+        match self.cancel_requested.load(Ordering::SeqCst) {
+            true => Err(LaunchCancelled.into()),
+            false => Ok(()),
+        }
+    }
+
+    async fn run_steps(&self, backend_slot: &mut Option<Box<dyn VRBackend + Send + Sync>>) -> anyhow::Result<()> {
+        let PreparedLaunch { steam_app, compat_info, prefix_id, launch_options, modifiers, backend } = {
+            let mut app_state = self.app_state.lock().await;
+            self.check_cancelled()?;
+            app_state.prepare_launch(&self.game)?
+        };
+        let backend = backend_slot.insert(backend);
+
+        if let Some(compat_info) = &compat_info {
+            self.install_verbs(&compat_info.version, &prefix_id).await?;
+        }
+
+        // Starting the backend can take a while, so it must not hold the lock
+        let (backend_log_channel, device_manager) = {
+            let mut app_state = self.enter_state(LaunchState::StartingBackend).await?;
+            let backend_log_channel = app_state.log_session.as_mut().unwrap()
+                .get_or_create_channel("vr_backend")?;
+            (backend_log_channel, app_state.device_manager.clone())
+        };
+
+        if backend.requires_adb_device() {
+            let device_manager = device_manager.lock().await;
+            let active_device = device_manager.get_selected_device_async().await?
+                .ok_or_else(|| anyhow::anyhow!("No VR device is selected"))?;
+
+            ensure!(
+                active_device.is_hmd_mounted()?,
+                "Please mount the headset before starting any games."
+            );
+        }
+
+        let start_info = backend.start_async(backend_log_channel, device_manager).await?;
+        {
+            let mut app_state = self.enter_state(LaunchState::WaitingForHeadset).await?;
+            if let Some(device_serial) = &start_info.vr_device_serial {
+                app_state.battery_monitor.set_active_device_serial(device_serial.clone());
+            }
+            if let Some(device_ip) = &start_info.vr_device_ip {
+                app_state.battery_monitor.set_active_device_ip(device_ip.clone());
+            }
+        }
+
+        let started_at = Instant::now();
+        while !backend.is_ready().await? {
+            self.check_cancelled()?;
+            ensure!(started_at.elapsed() < BACKEND_READY_TIMEOUT, "Could not start the backend.");
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        // Wait for virtual audio devices to be registered
+        drop(self.enter_state(LaunchState::ConfiguringAudio).await?);
+        let started_at = Instant::now();
+        loop {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+
+            let app_state = self.app_state.lock().await;
+            self.check_cancelled()?;
+            let output = app_state.audio_api.get_output_devices().into_iter()
+                .find(|d| backend.is_matching_audio_device(d));
+            let input = app_state.audio_api.get_input_devices().into_iter()
+                .find(|d| backend.is_matching_audio_device(d));
+
+            if let (Some(output_device), Some(input_device)) = (&output, &input) {
+                app_state.audio_api.set_default_output_device(output_device);
+                app_state.audio_api.set_default_input_device(input_device);
+                println!("Changed default output devices to '{}' / '{}'", output_device.description, input_device.description);
+                break;
+            } else if started_at.elapsed() > AUDIO_DEVICE_TIMEOUT {
+                eprintln!("Couldn't find the virtual audio devices for this backend!");
+                break;
+            }
+        }
+
+        // The last step holds the lock until the game is running, so it can no longer be cancelled
+        let mut app_state = self.enter_state(LaunchState::StartingOverlay).await?;
+        if start_info.was_restarted && self.game.use_overlay {
+            let overlay_log_channel = app_state.log_session.as_mut().unwrap()
+                .create_channel("overlay")?;
+            app_state.overlay_manager.start(overlay_log_channel)?;
+        }
+
+        app_state.spawn_game(self.game.clone(), steam_app, compat_info, modifiers, &launch_options, start_info.vr_device_serial)?;
+        app_state.active_backend = backend_slot.take();
+        app_state.set_launch_state(&self.launch_id, LaunchState::Running, None);
+
+        Ok(())
+    }
+
+    /// Installs the verbs the prefix does not have yet. Each verb is recorded once it has been
+    /// installed, so a cancelled or failed launch continues with the remaining ones next time.
+    async fn install_verbs(&self, proton: &ProtonVersion, prefix_id: &str) -> anyhow::Result<()> {
+        let missing_verbs = {
+            let app_state = self.app_state.lock().await;
+            let connection = &mut app_state.db_pool.get()?;
+            winetricks::find_missing_verbs(connection, prefix_id, &self.game.winetricks_verbs)?
+        };
+        if missing_verbs.is_empty() {
+            return Ok(());
+        }
+
+        let log_channel = self.enter_state(LaunchState::InstallingVerbs).await?
+            .log_session.as_mut().unwrap()
+            .create_channel("winetricks")?;
+
+        winetricks::initialize_prefix(proton, prefix_id, log_channel.clone()).await?;
+        for verb in missing_verbs {
+            self.check_cancelled()?;
+            winetricks::install_verb(proton, prefix_id, &verb, log_channel.clone()).await?;
+
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+            let app_state = self.app_state.lock().await;
+            let connection = &mut app_state.db_pool.get()?;
+            winetricks::record_installed_verb(connection, prefix_id, &verb, now)?;
+        }
+
+        Ok(())
+    }
+}
@@ -57,10 +57,41 @@ impl LogSession {
         Ok(channel)
     }
 
+    /// Returns the channel if it exists already, e.g. because it was handed over from the previous session
+    pub fn get_or_create_channel(&mut self, name: &str) -> anyhow::Result<Arc<Mutex<LogChannel>>> {
+        match self.channels.get(name) {
+            Some(channel) => Ok(channel.clone()),
+            None => self.create_channel(name),
+        }
+    }
+
+    /// Moves the matching channels into `session`, for processes that outlive this session and keep
+    /// writing to them. They are archived with `session` instead.
+    pub fn hand_over_channels(&mut self, session: &mut LogSession, filter: impl Fn(&str) -> bool) {
+        let names = self.channels.keys()
+            .filter(|name| filter(name))
+            .cloned()
+            .collect::<Vec<_>>();
+        for name in names {
+            let channel = self.channels.remove(&name).unwrap();
+            session.channels.insert(name, channel);
+        }
+    }
+
+    /// The files of every channel in this session, which are still being written to
+    pub fn channel_files(&self) -> Vec<PathBuf> {
+        self.channels.values()
+            .map(|channel| channel.lock().expect("Failed to lock the log channel").file_path.clone())
+            .collect()
+    }
+
     pub fn archive_old_files(&mut self) -> anyhow::Result<()> {
         let regex = Regex::new(r"^(\d{4}-\d{2}-\d{2}_\d{2}:\d{2}:\d{2})_(.+?)\.log$")?;
+        // Channels handed over from the previous session are still being written to
+        let channel_files = self.channel_files();
         let files = fs::read_dir(self.logs_dir.as_path())?
             .filter_map(Result::ok)
+            .filter(|entry| !channel_files.contains(&entry.path()))
             .map(|entry| (entry.path(), entry.file_name().to_str().unwrap().to_string()))
             .collect::<Vec<_>>();
 
@@ -103,9 +134,7 @@ impl LogSession {
 
         // Archive old log files
         if !log_paths.is_empty() {
-            let datetime: DateTime<Utc> = self.start_time.into();
-            let archive_filename = format!("{}.tar.gz", datetime.format("%Y-%m-%d_%H:%M:%S"));
-            let archive_path = self.logs_dir.join(archive_filename);
+            let archive_path = self.logs_dir.join(self.archive_file_name());
 
             Self::create_tar_gz(&log_paths, archive_path)?;
 
@@ -117,6 +146,12 @@ impl LogSession {
         Ok(())
     }
 
+    /// The name of the archive the channels of this session are packed into on shutdown
+    pub fn archive_file_name(&self) -> String {
+        let datetime: DateTime<Utc> = self.start_time.into();
+        format!("{}.tar.gz", datetime.format("%Y-%m-%d_%H:%M:%S"))
+    }
+
     fn create_tar_gz(files: &[PathBuf], output_path: PathBuf) -> anyhow::Result<()> {
         let tar_gz = File::create(output_path)?;
         let enc = GzEncoder::new(tar_gz, Compression::default());
@@ -12,21 +12,31 @@ mod overlay;
 mod logging;
 mod adb;
 mod perf;
+mod cover_art;
+mod database;
+mod events;
+mod idempotency;
+mod launch;
+mod diagnostics;
 
 use self::models::*;
 use crate::adb::device_manager::DeviceManager;
 use crate::app_state::AppState;
 use crate::audio_api::{DeviceChangeEvent, PipeWireManager};
+use crate::backends::registry::BackendRegistry;
 use crate::backends::BackendType;
+use crate::events::{GameSessionInfo, ServerEvent};
 use crate::battery_monitor::BatteryMonitor;
 use crate::overlay::WlxOverlayManager;
 use crate::steam::launcher::{CompatLauncher, ProcessHandle};
+use crate::steam::library_import::SteamImportJob;
 use axum::http::{header, HeaderValue};
-use axum::routing::{get, post};
+use axum::extract::DefaultBodyLimit;
+use axum::routing::{delete, get, post};
 use axum::Router;
 use image::ImageFormat;
 use serde::Serialize;
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::sync::Arc;
 use std::thread;
 use steam::steam_interface::SteamInterface;
@@ -50,7 +60,23 @@ pub struct GameSession {
     #[serde(skip)]
     process_handle: ProcessHandle,
     start_time_epoch: u64,
-    vr_device_serial: String,
+    vr_device_serial: Option<String>,
+    /// Set when the game is stopped to switch to another game on the same backend
+    #[serde(skip)]
+    keep_backend_on_exit: bool,
+    /// Set when the launcher is stopping the game, so its exit is not reported as a crash
+    #[serde(skip)]
+    stop_requested: bool,
+}
+
+impl GameSession {
+    pub fn info(&self) -> GameSessionInfo {
+        GameSessionInfo {
+            game: self.game.clone(),
+            start_time_epoch: self.start_time_epoch,
+            vr_device_serial: self.vr_device_serial.clone(),
+        }
+    }
 }
 
 #[tokio::main]
@@ -100,10 +126,11 @@ async fn main() -> anyhow::Result<()> {
         gtk::main();
     });
 
+    let db_pool = database::create_pool()?;
     let steam_api = SteamInterface::new();
     let launcher = Arc::new(CompatLauncher::new());
 
-    let (sock_tx, _) = broadcast::channel::<String>(100);
+    let (sock_tx, _) = broadcast::channel::<ServerEvent>(100);
     let audio_api = PipeWireManager::new()?;
     let mut device_changes = audio_api.subscribe_to_changes();
 
@@ -121,12 +148,9 @@ async fn main() -> anyhow::Result<()> {
                     match event_result {
                         Ok(event) => {
                             let message = match event {
-                                DeviceChangeEvent::DefaultInputChanged(device) =>
-                                    format!("default_input_changed:{}", serde_json::to_string(&device).unwrap()),
-                                DeviceChangeEvent::DefaultOutputChanged(device) =>
-                                    format!("default_output_changed:{}", serde_json::to_string(&device).unwrap()),
-                                DeviceChangeEvent::VolumeMuteChanged(device) =>
-                                    format!("volume_mute_changed:{}", serde_json::to_string(&device).unwrap()),
+                                DeviceChangeEvent::DefaultInputChanged(device) => ServerEvent::DefaultInputChanged(device),
+                                DeviceChangeEvent::DefaultOutputChanged(device) => ServerEvent::DefaultOutputChanged(device),
+                                DeviceChangeEvent::VolumeMuteChanged(device) => ServerEvent::VolumeMuteChanged(device),
                             };
                             let _ = ws_tx_clone.send(message);
                         }
@@ -143,8 +167,9 @@ async fn main() -> anyhow::Result<()> {
     let (socket_stop_tx, _) = broadcast::channel::<()>(1);
     let (bat_mon_stop_tx, _) = broadcast::channel::<()>(1);
     let (device_mon_stop_tx, _) = broadcast::channel::<()>(1);
-    let device_manager = Arc::new(Mutex::new(DeviceManager::new(device_mon_stop_tx.clone())?));
+    let device_manager = Arc::new(Mutex::new(DeviceManager::new(device_mon_stop_tx.clone(), db_pool.clone())?));
     let ws_tx_clone = sock_tx.clone();
+    let queue_events_rx = sock_tx.subscribe();
     let app_state = Arc::new(Mutex::new(AppState {
         audio_api,
         steam_api,
@@ -153,30 +178,70 @@ async fn main() -> anyhow::Result<()> {
         sock_tx,
         socket_stop_tx: socket_stop_tx.clone(),
         active_backend: None,
+        active_backend_config: None,
         device_manager: device_manager.clone(),
         backend_type: BackendType::Unknown,
+        backend_registry: BackendRegistry::new(),
         battery_monitor: BatteryMonitor::new(ws_tx_clone, device_manager.clone(), bat_mon_stop_tx.clone()),
         overlay_manager: WlxOverlayManager::new(),
         log_session: None,
-        launch_requests: HashSet::new(),
+        active_launch: None,
+        launch_queue: VecDeque::new(),
+        backend_health: None,
+        busy_prefixes: HashSet::new(),
+        steam_import: SteamImportJob::new(),
+        db_pool,
     }));
 
     launcher.set_app_state_async(app_state.clone()).await;
+    launch::spawn_queue_runner(app_state.clone(), queue_events_rx);
+    backends::supervisor::spawn_backend_supervisor(app_state.clone());
 
     let app_state_clone = app_state.clone();
     let app = Router::new()
-        .route("/api/games", get(routes::games::list_games))
-        .route("/api/games/{game_id}", get(routes::games::get_game_info))
-        .route("/api/games/{game_id}/cover", get(routes::games::get_game_cover))
+        .route("/api/games", get(routes::games::list_games)
+            .post(routes::games::create_game))
+        .route("/api/games/{game_id}", get(routes::games::get_game_info)
+            .put(routes::games::replace_game)
+            .patch(routes::games::patch_game)
+            .delete(routes::games::delete_game))
+        .route("/api/games/{game_id}/cover", get(routes::games::get_game_cover)
+            .put(routes::games::upload_game_cover)
+            .delete(routes::games::delete_game_cover)
+            .layer(DefaultBodyLimit::max(routes::games::MAX_COVER_SIZE_BYTES)))
+        .route("/api/games/{game_id}/cover/thumbnail", get(routes::games::get_game_cover_thumbnail))
+        .route("/api/games/{game_id}/prefix", get(routes::prefixes::get_prefix_info))
+        .route("/api/games/{game_id}/prefix/reset", post(routes::prefixes::reset_prefix))
+        .route("/api/games/{game_id}/prefix/backups", get(routes::prefixes::list_prefix_backups)
+            .post(routes::prefixes::create_prefix_backup))
+        .route("/api/games/{game_id}/prefix/backups/{file_name}/restore", post(routes::prefixes::restore_prefix_backup))
+        .route("/api/games/{game_id}/stats", get(routes::sessions::get_game_stats))
+        .route("/api/games/{game_id}/sessions", get(routes::sessions::list_game_sessions))
+        .route("/api/sessions", get(routes::sessions::list_all_sessions))
+        .route("/api/sessions/crashes", get(routes::sessions::list_crashed_sessions))
+        .route("/api/diagnostics/{file_name}", get(routes::diagnostics::download_bundle))
         .route("/api/games/{game_id}/launch", post(routes::game_state::launch_game_async))
+        .route("/api/games/{game_id}/switch", post(routes::game_state::switch_game_async))
+        .route("/api/launches/{launch_id}/cancel", post(routes::game_state::cancel_launch_async))
         .route("/api/games/active", get(routes::game_state::get_active_game))
         .route("/api/games/active/kill", post(routes::game_state::kill_active_game))
         .route("/api/games/reload_backend", post(routes::game_state::reload_backend))
+        .route("/api/backends", get(routes::backends::list_backends))
         .route("/api/audio/{endpoint}", get(routes::audio::get_audio_endpoints))
         .route("/api/audio/{endpoint}/{endpoint_id}/default", post(routes::audio::set_default_audio_endpoint))
         .route("/api/audio/device/{endpoint_id}/volume", post(routes::audio::set_audio_endpoint_volume))
         .route("/api/sock", get(routes::sock::sock_state_handler))
         .route("/api/device/battery", get(routes::device::get_battery_status))
+        .route("/api/devices", get(routes::device::list_devices))
+        .route("/api/devices/discover", get(routes::device::discover_devices))
+        .route("/api/devices/pair", post(routes::device::pair_device))
+        .route("/api/devices/connect", post(routes::device::connect_device))
+        .route("/api/devices/{serial}", delete(routes::device::forget_device))
+        .route("/api/devices/{serial}/select", post(routes::device::select_device))
+        .route("/api/steam/importable", get(routes::steam::list_importable_apps))
+        .route("/api/steam/apps/{app_id}/launch_configs", get(routes::steam::list_launch_configs))
+        .route("/api/steam/import", get(routes::steam::get_import_status)
+            .post(routes::steam::start_import))
         .route("/api/debug/agent", get(routes::debug::get_user_agent))
         .route("/{path}", get(routes::frontend::get_frontend_asset))
         .fallback(get(routes::frontend::get_frontend_asset))
@@ -1,10 +1,13 @@
-use std::env;
-use diesel::{Queryable, Selectable, SqliteConnection, Connection};
-use dotenvy::dotenv;
-use serde::Serialize;
+use diesel::{AsChangeset, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Deserializer, Serialize};
 use ts_rs::TS;
+use crate::backends::registry::BackendRegistry;
+use crate::command_parser::parse_linux_command;
+use crate::steam::launch_options::LaunchOptions;
+use crate::steam::steam_interface::ProtonVersion;
+use crate::steam::winetricks;
 
-#[derive(Debug, Queryable, Selectable, Serialize, TS)]
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, TS)]
 #[ts(export, export_to = "rust_bindings.ts")]
 #[serde(rename_all = "camelCase")]
 #[diesel(table_name = crate::schema::games)]
@@ -13,6 +16,7 @@ pub struct Game {
     pub id: String,
     pub title: String,
     #[serde(skip_serializing)]
+    #[ts(skip)]
     pub cover: Option<Vec<u8>>,
     pub vr_backend: String,
     pub vr_backend_args: String,
@@ -21,12 +25,392 @@ pub struct Game {
     pub proton_version: Option<String>,
     pub command_line: Option<String>,
     pub use_overlay: bool,
+    /// Runs the game in its own transient systemd user scope, when systemd is available
+    pub use_systemd_scope: bool,
+    /// Assigned on the first launch, see `prefix::ensure_prefix_id`
+    pub prefix_id: Option<String>,
+    /// Space-separated winetricks verbs that are installed into the prefix before launching
+    pub winetricks_verbs: String,
+    /// Steam-style launch options, e.g. `gamemoderun %command% -vr`, see `LaunchOptions`
+    pub launch_options: String,
+    /// The `config.launch` entry Steam games are started with, by index or description.
+    /// `None` picks the first entry for the platform, preferring VR entries.
+    pub steam_launch_config: Option<String>,
+}
+
+/// Every user-editable column of a game, as accepted by the create and update endpoints
+#[derive(Debug, Clone, Deserialize, Insertable, AsChangeset)]
+#[serde(rename_all = "camelCase")]
+#[diesel(table_name = crate::schema::games)]
+#[diesel(treat_none_as_null = true)]
+pub struct GameFields {
+    pub title: String,
+    pub vr_backend: String,
+    #[serde(default)]
+    pub vr_backend_args: String,
+    #[serde(default)]
+    pub pressure_vessel: bool,
+    #[serde(default)]
+    pub steam_app_id: Option<i64>,
+    #[serde(default)]
+    pub proton_version: Option<String>,
+    #[serde(default)]
+    pub command_line: Option<String>,
+    #[serde(default)]
+    pub use_overlay: bool,
+    #[serde(default)]
+    pub use_systemd_scope: bool,
+    #[serde(default)]
+    pub winetricks_verbs: String,
+    #[serde(default)]
+    pub launch_options: String,
+    #[serde(default)]
+    pub steam_launch_config: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::games)]
+pub struct NewGame {
+    pub id: String,
+    #[diesel(embed)]
+    pub fields: GameFields,
+}
+
+/// Partial update of a game; nullable columns use `Some(None)` to clear the value
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GamePatch {
+    pub title: Option<String>,
+    pub vr_backend: Option<String>,
+    pub vr_backend_args: Option<String>,
+    pub pressure_vessel: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub steam_app_id: Option<Option<i64>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub proton_version: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub command_line: Option<Option<String>>,
+    pub use_overlay: Option<bool>,
+    pub use_systemd_scope: Option<bool>,
+    pub winetricks_verbs: Option<String>,
+    pub launch_options: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub steam_launch_config: Option<Option<String>>,
+}
+
+impl From<&Game> for GameFields {
+    fn from(game: &Game) -> Self {
+        Self {
+            title: game.title.clone(),
+            vr_backend: game.vr_backend.clone(),
+            vr_backend_args: game.vr_backend_args.clone(),
+            pressure_vessel: game.pressure_vessel,
+            steam_app_id: game.steam_app_id,
+            proton_version: game.proton_version.clone(),
+            command_line: game.command_line.clone(),
+            use_overlay: game.use_overlay,
+            use_systemd_scope: game.use_systemd_scope,
+            winetricks_verbs: game.winetricks_verbs.clone(),
+            launch_options: game.launch_options.clone(),
+            steam_launch_config: game.steam_launch_config.clone(),
+        }
+    }
+}
+
+impl GameFields {
+    pub fn apply_patch(&mut self, patch: GamePatch) {
+        if let Some(title) = patch.title {
+            self.title = title;
+        }
+        if let Some(vr_backend) = patch.vr_backend {
+            self.vr_backend = vr_backend;
+        }
+        if let Some(vr_backend_args) = patch.vr_backend_args {
+            self.vr_backend_args = vr_backend_args;
+        }
+        if let Some(pressure_vessel) = patch.pressure_vessel {
+            self.pressure_vessel = pressure_vessel;
+        }
+        if let Some(steam_app_id) = patch.steam_app_id {
+            self.steam_app_id = steam_app_id;
+        }
+        if let Some(proton_version) = patch.proton_version {
+            self.proton_version = proton_version;
+        }
+        if let Some(command_line) = patch.command_line {
+            self.command_line = command_line;
+        }
+        if let Some(use_overlay) = patch.use_overlay {
+            self.use_overlay = use_overlay;
+        }
+        if let Some(use_systemd_scope) = patch.use_systemd_scope {
+            self.use_systemd_scope = use_systemd_scope;
+        }
+        if let Some(winetricks_verbs) = patch.winetricks_verbs {
+            self.winetricks_verbs = winetricks_verbs;
+        }
+        if let Some(launch_options) = patch.launch_options {
+            self.launch_options = launch_options;
+        }
+        if let Some(steam_launch_config) = patch.steam_launch_config {
+            self.steam_launch_config = steam_launch_config;
+        }
+    }
+
+    /// Checks that the game could actually be launched with these settings.
+    /// Returns every problem that was found, not just the first one.
+    pub fn validate(&self, proton_versions: &[ProtonVersion], backends: &BackendRegistry) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if self.title.trim().is_empty() {
+            errors.push("The title cannot be empty".to_string());
+        }
+
+        if let Err(error) = backends.validate(&self.vr_backend, &self.vr_backend_args) {
+            errors.push(error.to_string());
+        }
+
+        if let Some(proton_version) = &self.proton_version
+            && !proton_versions.iter().any(|p| p.name == *proton_version) {
+            errors.push(format!("Unknown Proton version: '{}'", proton_version));
+        }
+
+        if let Some(steam_app_id) = self.steam_app_id
+            && (steam_app_id <= 0 || steam_app_id > u32::MAX as i64) {
+            errors.push(format!("Invalid Steam app id: {}", steam_app_id));
+        }
+
+        if let Err(error) = winetricks::validate_verbs(&self.winetricks_verbs) {
+            errors.push(error);
+        } else if !self.winetricks_verbs.trim().is_empty() && self.proton_version.is_none() {
+            errors.push("Winetricks verbs can only be installed for games that run with Proton".to_string());
+        }
+
+        if self.steam_launch_config.is_some() && (self.steam_app_id.is_none() || self.command_line.is_some()) {
+            errors.push("A Steam launch config can only be selected for games that are started through Steam".to_string());
+        }
+
+        if let Err(error) = LaunchOptions::parse(&self.launch_options) {
+            errors.push(format!("Invalid launch options: {}", error));
+        }
+
+        match &self.command_line {
+            Some(command_line) => {
+                if let Err(error) = parse_linux_command(command_line) {
+                    errors.push(format!("Invalid command line: {}", error));
+                }
+            }
+            None if self.steam_app_id.is_none() => {
+                errors.push("Either a Steam app id or a command line must be specified".to_string());
+            }
+            None => {}
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+}
+
+#[derive(Debug, Queryable, Selectable, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+#[diesel(table_name = crate::schema::sessions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Session {
+    pub id: i32,
+    pub game_id: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub exit_code: Option<i32>,
+    pub exit_signal: Option<i32>,
+    pub vr_backend: String,
+    pub device_serial: Option<String>,
+    pub log_archive: Option<String>,
+    /// Only known for games that ran in a systemd scope
+    pub cpu_time_ms: Option<i64>,
+    pub peak_memory_bytes: Option<i64>,
+    /// Set when the game has exited abnormally, see `CrashKind`
+    pub crash_kind: Option<String>,
+    pub diagnostics_bundle: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::sessions)]
+pub struct NewSession {
+    pub game_id: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub exit_code: Option<i32>,
+    pub exit_signal: Option<i32>,
+    pub vr_backend: String,
+    pub device_serial: Option<String>,
+    pub log_archive: Option<String>,
+    pub cpu_time_ms: Option<i64>,
+    pub peak_memory_bytes: Option<i64>,
+    pub crash_kind: Option<String>,
+    pub diagnostics_bundle: Option<String>,
 }
 
-pub fn establish_connection() -> SqliteConnection {
-    dotenv().ok();
+/// A headset that was added over the network, reconnected automatically on startup
+#[derive(Debug, Clone, Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::known_devices)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct KnownDevice {
+    pub serial: String,
+    pub manufacturer: String,
+    pub product_name: String,
+    pub network_address: String,
+}
+
+/// The idempotency token of a launch request, so a retried request gets the original launch's result
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::launch_requests)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct LaunchRequest {
+    pub token: String,
+    pub launch_id: String,
+    pub game_id: String,
+    /// `None` while the launch is still in progress
+    pub outcome: Option<String>,
+    pub error: Option<String>,
+    pub created_at: i64,
+}
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    SqliteConnection::establish(&database_url)
-        .unwrap_or_else(|_| panic!("Error connecting to {}", database_url))
-}
\ No newline at end of file
+/// A winetricks verb that has been installed into a Proton prefix
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::installed_verbs)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct InstalledVerb {
+    pub prefix_id: String,
+    pub verb: String,
+    pub installed_at: i64,
+}
+
+/// Distinguishes a missing field (`None`) from an explicit `null` (`Some(None)`)
+fn deserialize_nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn steam_game() -> GameFields {
+        GameFields {
+            title: "Beat Saber".into(),
+            vr_backend: "wivrn".into(),
+            vr_backend_args: String::new(),
+            pressure_vessel: false,
+            steam_app_id: Some(620980),
+            proton_version: Some("Proton 9.0".into()),
+            command_line: None,
+            use_overlay: false,
+            use_systemd_scope: false,
+            winetricks_verbs: String::new(),
+            launch_options: String::new(),
+            steam_launch_config: None,
+        }
+    }
+
+    fn validate(fields: &GameFields) -> Result<(), Vec<String>> {
+        let proton_versions = [ProtonVersion {
+            steam_id: None,
+            name: "Proton 9.0".into(),
+            executable_path: PathBuf::from("/opt/proton/proton"),
+        }];
+
+        fields.validate(&proton_versions, &BackendRegistry::new())
+    }
+
+    #[test]
+    fn test_validate_accepts_valid_games() {
+        assert!(validate(&steam_game()).is_ok());
+
+        let native_game = GameFields {
+            steam_app_id: None,
+            proton_version: None,
+            command_line: Some("/usr/bin/game --vr".into()),
+            ..steam_game()
+        };
+        assert!(validate(&native_game).is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_combinations() {
+        let rejected = [
+            GameFields { title: "  ".into(), ..steam_game() },
+            GameFields { vr_backend: "steamvr".into(), ..steam_game() },
+            GameFields { proton_version: Some("Proton 1.0".into()), ..steam_game() },
+            GameFields { steam_app_id: Some(0), ..steam_game() },
+            GameFields { steam_app_id: Some(u32::MAX as i64 + 1), ..steam_game() },
+            GameFields { steam_app_id: None, command_line: None, ..steam_game() },
+            GameFields { command_line: Some("\"unclosed".into()), ..steam_game() },
+            GameFields { launch_options: "%command% %command%".into(), ..steam_game() },
+            GameFields { winetricks_verbs: "--force".into(), ..steam_game() },
+            GameFields { winetricks_verbs: "vcrun2019".into(), proton_version: None, ..steam_game() },
+            GameFields { steam_launch_config: Some("1".into()), steam_app_id: None, command_line: Some("/usr/bin/game".into()), ..steam_game() },
+            GameFields { steam_launch_config: Some("1".into()), command_line: Some("/usr/bin/game".into()), ..steam_game() },
+        ];
+
+        for fields in rejected {
+            assert!(validate(&fields).is_err(), "{:?} should be rejected", fields);
+        }
+    }
+
+    #[test]
+    fn test_validate_reports_every_error() {
+        let fields = GameFields {
+            title: String::new(),
+            steam_app_id: Some(-1),
+            ..steam_game()
+        };
+
+        assert_eq!(validate(&fields).unwrap_err().len(), 2);
+    }
+
+    #[test]
+    fn test_patch_keeps_missing_fields() {
+        let mut fields = steam_game();
+        let patch: GamePatch = serde_json::from_str(r#"{"title": "Beat Saber (beta)"}"#).unwrap();
+        fields.apply_patch(patch);
+
+        assert_eq!(fields.title, "Beat Saber (beta)");
+        assert_eq!(fields.steam_app_id, Some(620980));
+        assert_eq!(fields.proton_version.as_deref(), Some("Proton 9.0"));
+        assert_eq!(fields.command_line, None);
+    }
+
+    #[test]
+    fn test_patch_null_clears_nullable_fields() {
+        let mut fields = GameFields {
+            command_line: Some("/usr/bin/game".into()),
+            steam_launch_config: Some("1".into()),
+            ..steam_game()
+        };
+        let patch: GamePatch = serde_json::from_str(r#"{"steamAppId": null, "protonVersion": null, "steamLaunchConfig": null}"#).unwrap();
+        fields.apply_patch(patch);
+
+        assert_eq!(fields.steam_app_id, None);
+        assert_eq!(fields.proton_version, None);
+        assert_eq!(fields.steam_launch_config, None);
+        assert_eq!(fields.command_line.as_deref(), Some("/usr/bin/game"));
+    }
+
+    #[test]
+    fn test_patch_sets_nullable_fields() {
+        let mut fields = steam_game();
+        let patch: GamePatch = serde_json::from_str(r#"{"commandLine": "/usr/bin/game", "steamLaunchConfig": "Launch in VR"}"#).unwrap();
+        fields.apply_patch(patch);
+
+        assert_eq!(fields.command_line.as_deref(), Some("/usr/bin/game"));
+        assert_eq!(fields.steam_launch_config.as_deref(), Some("Launch in VR"));
+        assert_eq!(fields.steam_app_id, Some(620980));
+    }
+}
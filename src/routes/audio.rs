@@ -3,17 +3,26 @@ use axum::http::StatusCode;
 use axum::Json;
 use axum::response::IntoResponse;
 use serde::Deserialize;
-use crate::app_state::AppStateWrapper;
+use ts_rs::TS;
+use crate::app_state::{AppState, AppStateWrapper};
+use crate::routes::api_error::{ApiError, ApiResult};
+
+#[derive(Debug, Clone, Copy, Deserialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub enum AudioEndpoint {
+    Inputs,
+    Outputs,
+}
 
 pub async fn get_audio_endpoints(
     State(app_state): State<AppStateWrapper>,
-    Path(endpoint): Path<String>
+    Path(endpoint): Path<AudioEndpoint>
 ) -> impl IntoResponse {
     let app_state = app_state.lock().await;
-    let mut devices = match endpoint.as_str() {
-        "inputs" => app_state.audio_api.get_input_devices(),
-        "outputs" => app_state.audio_api.get_output_devices(),
-        _ => return StatusCode::BAD_REQUEST.into_response(),
+    let mut devices = match endpoint {
+        AudioEndpoint::Inputs => app_state.audio_api.get_input_devices(),
+        AudioEndpoint::Outputs => app_state.audio_api.get_output_devices(),
     }.into_iter().collect::<Vec<_>>();
 
     devices.sort_by_key(|d| d.name.clone());
@@ -23,43 +32,50 @@ pub async fn get_audio_endpoints(
 
 pub async fn set_default_audio_endpoint(
     State(app_state): State<AppStateWrapper>,
-    Path((endpoint, endpoint_id)): Path<(String, u32)>
-) -> impl IntoResponse {
-    let app_state = app_state.lock().await;
-    let devices = match endpoint.as_str() {
-        "inputs" => app_state.audio_api.get_input_devices(),
-        "outputs" => app_state.audio_api.get_output_devices(),
-        _ => return StatusCode::BAD_REQUEST.into_response(),
-    };
+    Path((endpoint, endpoint_id)): Path<(AudioEndpoint, u32)>
+) -> ApiResult<StatusCode> {
+    set_default_device(&*app_state.lock().await, endpoint, endpoint_id)?;
 
-    match devices.iter().find(|d| d.id == endpoint_id) {
-        Some(device) => {
-            match endpoint.as_str() {
-                "inputs" => app_state.audio_api.set_default_input_device(device),
-                "outputs" => app_state.audio_api.set_default_output_device(device),
-                _ => return StatusCode::BAD_REQUEST.into_response(),
-            }
-            StatusCode::NO_CONTENT.into_response()
-        }
-        None => StatusCode::NOT_FOUND.into_response(),
-    }
+    Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn set_audio_endpoint_volume(
     State(app_state): State<AppStateWrapper>,
     Path(endpoint_id): Path<u32>,
     Json(payload): Json<AudioVolumeControl>
-) -> impl IntoResponse {
-    let app_state = app_state.lock().await;
-    let mut all_devices = app_state.audio_api.get_input_devices().clone();
-    all_devices.extend(app_state.audio_api.get_output_devices());
+) -> ApiResult<StatusCode> {
+    set_device_volume(&*app_state.lock().await, endpoint_id, payload.volume, payload.muted)?;
 
-    let device = all_devices.iter().find(|d| d.id == endpoint_id);
-    if let Some(device) = device {
-        app_state.audio_api.set_device_volume(device, payload.volume, payload.muted);
+    Ok(StatusCode::OK)
+}
+
+pub fn set_default_device(app_state: &AppState, endpoint: AudioEndpoint, device_id: u32) -> ApiResult<()> {
+    let devices = match endpoint {
+        AudioEndpoint::Inputs => app_state.audio_api.get_input_devices(),
+        AudioEndpoint::Outputs => app_state.audio_api.get_output_devices(),
+    };
+
+    let device = devices.iter()
+        .find(|d| d.id == device_id)
+        .ok_or(ApiError::NotFound)?;
+    match endpoint {
+        AudioEndpoint::Inputs => app_state.audio_api.set_default_input_device(device),
+        AudioEndpoint::Outputs => app_state.audio_api.set_default_output_device(device),
     }
 
-    StatusCode::OK.into_response()
+    Ok(())
+}
+
+pub fn set_device_volume(app_state: &AppState, device_id: u32, volume: u8, muted: bool) -> ApiResult<()> {
+    let mut all_devices = app_state.audio_api.get_input_devices();
+    all_devices.extend(app_state.audio_api.get_output_devices());
+
+    let device = all_devices.iter()
+        .find(|d| d.id == device_id)
+        .ok_or(ApiError::NotFound)?;
+    app_state.audio_api.set_device_volume(device, volume, muted);
+
+    Ok(())
 }
 
 #[derive(Deserialize, Debug)]
@@ -0,0 +1,10 @@
+use axum::extract::State;
+use axum::Json;
+use crate::app_state::AppStateWrapper;
+use crate::backends::registry::BackendInfo;
+use crate::routes::api_error::ApiResult;
+
+pub async fn list_backends(State(app_state): State<AppStateWrapper>) -> ApiResult<Json<Vec<BackendInfo>>> {
+    let app_state = app_state.lock().await;
+    Ok(Json(app_state.backend_registry.list()))
+}
@@ -1,8 +1,12 @@
-use axum::extract::State;
+use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::Json;
 use axum::response::IntoResponse;
+use crate::adb::device_manager::VrDeviceInfo;
+use crate::adb::wireless;
+use crate::adb::wireless::{AdbMdnsService, ConnectRequest, PairRequest};
 use crate::app_state::AppStateWrapper;
+use crate::routes::api_error::{ApiError, ApiResult};
 
 pub async fn get_battery_status(State(app_state): State<AppStateWrapper>) -> impl IntoResponse {
     let app_state = app_state.lock().await;
@@ -12,4 +16,67 @@ pub async fn get_battery_status(State(app_state): State<AppStateWrapper>) -> imp
         Some(info) => Json(info).into_response(),
         None => StatusCode::NOT_FOUND.into_response(),
     }
-}
\ No newline at end of file
+}
+
+pub async fn list_devices(State(app_state): State<AppStateWrapper>) -> Json<Vec<VrDeviceInfo>> {
+    let device_manager = app_state.lock().await.device_manager.clone();
+    let device_manager = device_manager.lock().await;
+
+    Json(device_manager.list_devices_async().await)
+}
+
+pub async fn select_device(
+    State(app_state): State<AppStateWrapper>,
+    Path(serial): Path<String>,
+) -> ApiResult<StatusCode> {
+    let app_state = app_state.lock().await;
+    if app_state.active_game_session.is_some() {
+        return Err(ApiError::Conflict("The headset cannot be switched while a game is running".into()));
+    }
+
+    let device_manager = app_state.device_manager.lock().await;
+    if !device_manager.list_devices_async().await.iter().any(|d| d.serial == serial) {
+        return Err(ApiError::NotFound);
+    }
+    device_manager.select_device_async(&serial).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn discover_devices() -> ApiResult<Json<Vec<AdbMdnsService>>> {
+    let services = tokio::task::spawn_blocking(wireless::discover_services).await??;
+    Ok(Json(services))
+}
+
+pub async fn pair_device(Json(request): Json<PairRequest>) -> ApiResult<StatusCode> {
+    tokio::task::spawn_blocking(move || wireless::pair(&request.address, &request.pairing_code))
+        .await?
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn connect_device(
+    State(app_state): State<AppStateWrapper>,
+    Json(request): Json<ConnectRequest>,
+) -> ApiResult<Json<VrDeviceInfo>> {
+    let device_manager = app_state.lock().await.device_manager.clone();
+    let device_manager = device_manager.lock().await;
+    let device = device_manager.connect_network_device_async(&request.address).await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    Ok(Json(device))
+}
+
+pub async fn forget_device(
+    State(app_state): State<AppStateWrapper>,
+    Path(serial): Path<String>,
+) -> ApiResult<StatusCode> {
+    let device_manager = app_state.lock().await.device_manager.clone();
+    let device_manager = device_manager.lock().await;
+
+    match device_manager.forget_device_async(&serial).await? {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err(ApiError::NotFound),
+    }
+}
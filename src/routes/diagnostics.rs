@@ -0,0 +1,28 @@
+use axum::body::Body;
+use axum::extract::Path;
+use axum::http::{header, StatusCode};
+use axum::response::Response;
+use crate::diagnostics;
+use crate::routes::api_error::{ApiError, ApiResult};
+
+/// Downloads a bundle created for a crashed game, see `Session::diagnostics_bundle`
+pub async fn download_bundle(Path(file_name): Path<String>) -> ApiResult<Response> {
+    if !diagnostics::is_valid_bundle_name(&file_name) {
+        return Err(ApiError::BadRequest(format!("Invalid bundle name: '{}'", file_name)));
+    }
+
+    let bundle_path = diagnostics::diagnostics_dir()?.join(&file_name);
+    let data = match tokio::fs::read(&bundle_path).await {
+        Ok(data) => data,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Err(ApiError::NotFound),
+        Err(error) => return Err(anyhow::Error::from(error).into()),
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/gzip")
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name))
+        .header(header::CONTENT_LENGTH, data.len())
+        .body(Body::from(data))
+        .unwrap())
+}
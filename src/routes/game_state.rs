@@ -1,76 +1,130 @@
-use crate::app_state::AppStateWrapper;
-use crate::backends::BackendType;
-use crate::models::{establish_connection, Game};
-use crate::schema::games::dsl::games;
-use axum::body::Body;
+use crate::app_state::{AppState, AppStateWrapper};
+use crate::routes::api_error::{ApiError, ApiResult};
+use crate::routes::games::load_game;
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use diesel::{QueryDsl, RunQueryDsl, SelectableHelper};
+use axum::Json;
+use crate::launch::{GameExitInfo, LaunchStarted, LaunchState};
+use crate::idempotency::{self, LaunchOutcome, LaunchReplay};
+use crate::models::LaunchRequest;
+use crate::steam::process_tree::{GameProcessTree, DEFAULT_GRACE_PERIOD};
+use std::os::unix::process::ExitStatusExt;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use serde::Deserialize;
 
 #[derive(Deserialize)]
 pub struct LaunchQuery {
     idem_token: String,
+    /// Wait for the current session to end instead of failing while a game is running
+    #[serde(default)]
+    queue: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LaunchMode {
+    /// Fails if a game is already running
+    Start,
+    Queue,
+    /// Stops the running game first
+    Switch,
 }
 
 pub async fn launch_game_async(
     State(app_state): State<AppStateWrapper>,
     Path(game_id): Path<String>,
     query: Query<LaunchQuery>,
-) -> impl IntoResponse + Send {
-    let mut app_state = app_state.lock().await;
-    if app_state.launch_requests.contains(&query.idem_token) {
-        return StatusCode::NO_CONTENT.into_response();
-    }
-    app_state.launch_requests.insert(query.idem_token.clone());
+) -> ApiResult<Response> {
+    println!("[Axum/HTTP] Handling launch request");
+    let mode = match query.queue {
+        true => LaunchMode::Queue,
+        false => LaunchMode::Start,
+    };
 
-    let connection = &mut establish_connection();
-    let mut result = games
-        .select(Game::as_select())
-        .find(game_id)
-        .load(connection)
-        .expect("Error loading games");
+    handle_launch_request(&app_state, &game_id, &query.idem_token, mode).await
+}
 
-    println!("[Axum/HTTP] Handling launch request");
+pub async fn switch_game_async(
+    State(app_state): State<AppStateWrapper>,
+    Path(game_id): Path<String>,
+    query: Query<LaunchQuery>,
+) -> ApiResult<Response> {
+    println!("[Axum/HTTP] Handling switch request");
+    handle_launch_request(&app_state, &game_id, &query.idem_token, LaunchMode::Switch).await
+}
 
-    match result.pop() {
-        Some(game) => {
-            match app_state.launch_game_async(game).await {
-                Ok(_) => {
-                    Response::builder()
-                        .status(StatusCode::OK)
-                        .body(Body::empty())
-                        .unwrap()
-                }
-                Err(error) => {
-                    println!("{:?}", error);
-                    Response::builder()
-                        .status(StatusCode::INTERNAL_SERVER_ERROR)
-                        .body(Body::from(error.to_string()))
-                        .unwrap()
-                },
-            }
+async fn handle_launch_request(app_state: &AppStateWrapper, game_id: &str, idem_token: &str, mode: LaunchMode) -> ApiResult<Response> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_err(anyhow::Error::from)?.as_secs() as i64;
+    let pool = app_state.lock().await.db_pool.clone();
+    let connection = &mut pool.get()?;
+
+    // The lock is held until the request is recorded, so two requests with the same token cannot
+    // both start a launch, and a retried switch does not stop the game it has already launched
+    let (launch_id, process_tree) = {
+        let mut state = app_state.lock().await;
+        if let Some(request) = idempotency::find_request(connection, idem_token, now)? {
+            return replay_launch_request(&state, request);
         }
-        None => StatusCode::NOT_FOUND.into_response(),
+
+        // Rejected requests are not recorded, they had no effect and can simply be retried
+        let (launch_id, process_tree) = start_game_launch(&mut state, app_state, game_id, mode)?;
+        idempotency::record_request(connection, idem_token, &launch_id, game_id, now)?;
+        (launch_id, process_tree)
+    };
+
+    if let Some(process_tree) = process_tree {
+        terminate_game(app_state, process_tree, DEFAULT_GRACE_PERIOD).await?;
     }
+
+    Ok((StatusCode::ACCEPTED, Json(LaunchStarted { launch_id })).into_response())
+}
+
+/// A launch that is still in progress is answered like the original request, one that has ended
+/// with its outcome. Failures were recorded, so they are not errors of the retried request.
+fn replay_launch_request(app_state: &AppState, request: LaunchRequest) -> ApiResult<Response> {
+    let launch_id = request.launch_id;
+    let outcome = match request.outcome.as_deref().and_then(LaunchOutcome::parse) {
+        None if app_state.active_launch.as_ref().is_some_and(|l| l.progress.launch_id == launch_id)
+            || app_state.launch_queue.iter().any(|l| l.launch_id == launch_id) =>
+            return Ok((StatusCode::ACCEPTED, Json(LaunchStarted { launch_id })).into_response()),
+        Some(outcome) => outcome,
+        None => LaunchOutcome::Interrupted,
+    };
+
+    let (status, error) = match outcome {
+        LaunchOutcome::Started => (StatusCode::OK, None),
+        LaunchOutcome::Failed => (StatusCode::CONFLICT, Some(request.error.unwrap_or("The launch has failed".into()))),
+        LaunchOutcome::Cancelled => (StatusCode::CONFLICT, Some("The launch was cancelled".into())),
+        LaunchOutcome::Interrupted => (StatusCode::CONFLICT, Some("The launch was interrupted by a restart of the launcher".into())),
+    };
+
+    Ok((status, Json(LaunchReplay { launch_id, outcome, error })).into_response())
+}
+
+pub async fn cancel_launch_async(
+    State(app_state): State<AppStateWrapper>,
+    Path(launch_id): Path<String>,
+) -> ApiResult<StatusCode> {
+    cancel_launch(&mut *app_state.lock().await, &launch_id)?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[derive(Deserialize)]
+pub struct KillQuery {
+    /// How long the game gets to exit after SIGTERM before it is killed
+    grace_period_secs: Option<u64>,
 }
 
 pub async fn kill_active_game(
     State(app_state): State<AppStateWrapper>,
-) -> impl IntoResponse {
-    let mut app_state = app_state.lock().await;
-    match &mut app_state.active_game_session {
-        Some(_) => {
-            match app_state.kill_active_game() {
-                Ok(_) => StatusCode::NO_CONTENT.into_response(),
-                Err(error) => Response::builder()
-                    .status(StatusCode::INTERNAL_SERVER_ERROR)
-                    .body(Body::from(error.to_string()))
-                    .unwrap(),
-            }
-        }
-        None => StatusCode::NO_CONTENT.into_response()
+    query: Query<KillQuery>,
+) -> ApiResult<Response> {
+    let grace_period = query.grace_period_secs.map(Duration::from_secs).unwrap_or(DEFAULT_GRACE_PERIOD);
+    match kill_game(&app_state, grace_period).await? {
+        Some(exit_info) => Ok(Json(exit_info).into_response()),
+        None => Ok(StatusCode::NO_CONTENT.into_response()),
     }
 }
 
@@ -92,41 +146,91 @@ pub async fn get_active_game(State(_app_state): State<AppStateWrapper>) -> impl
                 cover: None,
                 total_playtime_sec: 0,
             },
-            vr_device_serial: "1WMHHA67UU2191".into(),
+            vr_device_serial: Some("1WMHHA67UU2191".into()),
         }).unwrap())*/
     }
 }
 
-pub async fn reload_backend(State(app_state): State<AppStateWrapper>) -> impl IntoResponse {
-    let mut app_state = app_state.lock().await;
+pub async fn reload_backend(State(app_state): State<AppStateWrapper>) -> ApiResult<StatusCode> {
+    reload_active_backend(&mut *app_state.lock().await).await?;
 
+    Ok(StatusCode::OK)
+}
+
+/// Starts the launch in the background and returns its id, progress is reported over the socket
+pub async fn launch_game(app_state: &AppStateWrapper, game_id: &str, mode: LaunchMode) -> ApiResult<String> {
+    let (launch_id, process_tree) = start_game_launch(&mut *app_state.lock().await, app_state, game_id, mode)?;
+    if let Some(process_tree) = process_tree {
+        terminate_game(app_state, process_tree, DEFAULT_GRACE_PERIOD).await?;
+    }
+
+    Ok(launch_id)
+}
+
+/// Returns the processes of the game a switch replaces, which the caller terminates without
+/// holding the lock. The queue runner starts the switch's launch once that game has exited.
+fn start_game_launch(state: &mut AppState, app_state: &AppStateWrapper, game_id: &str, mode: LaunchMode) -> ApiResult<(String, Option<GameProcessTree>)> {
+    let pool = state.db_pool.clone();
+    let connection = &mut pool.get()?;
+    let game = load_game(connection, game_id)?;
+
+    let result = match mode {
+        LaunchMode::Start => state.start_launch(app_state.clone(), game).map(|launch_id| (launch_id, None)),
+        LaunchMode::Queue => state.queue_launch(app_state.clone(), game).map(|launch_id| (launch_id, None)),
+        LaunchMode::Switch => state.begin_switch(app_state.clone(), game),
+    };
+    result.map_err(|error| ApiError::Conflict(error.to_string()))
+}
+
+/// A queued launch is dropped right away, a running one stops at its next step boundary.
+/// Either way the `Cancelled` state is reported.
+pub fn cancel_launch(app_state: &mut AppState, launch_id: &str) -> ApiResult<()> {
+    if app_state.cancel_queued_launch(launch_id) {
+        return Ok(());
+    }
+
+    let launch = app_state.active_launch.as_ref()
+        .filter(|launch| launch.progress.launch_id == launch_id)
+        .ok_or(ApiError::NotFound)?;
+
+    if matches!(launch.progress.state, LaunchState::Running | LaunchState::Stopping) {
+        return Err(ApiError::Conflict("The game is already running, kill it instead".into()));
+    }
+    launch.cancel_requested.store(true, Ordering::SeqCst);
+
+    Ok(())
+}
+
+/// Killing when nothing is running is not an error, the game may have just exited on its own.
+/// Returns how the game has exited, or `None` if nothing was running.
+pub async fn kill_game(app_state: &AppStateWrapper, grace_period: Duration) -> ApiResult<Option<GameExitInfo>> {
+    let process_tree = app_state.lock().await.begin_stopping_game(false);
+    match process_tree {
+        Some(process_tree) => terminate_game(app_state, process_tree, grace_period).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Ends the session right away, instead of waiting for the launcher to notice the exit
+async fn terminate_game(app_state: &AppStateWrapper, process_tree: GameProcessTree, grace_period: Duration) -> ApiResult<GameExitInfo> {
+    let exit_status = process_tree.terminate(grace_period).await;
+    app_state.lock().await.game_process_died(&process_tree.process_token, exit_status)?;
+
+    Ok(GameExitInfo {
+        exit_code: exit_status.and_then(|s| s.code()),
+        exit_signal: exit_status.and_then(|s| s.signal()),
+    })
+}
+
+pub async fn reload_active_backend(app_state: &mut AppState) -> ApiResult<()> {
     if app_state.active_game_session.is_none() {
-        return Response::builder()
-            .status(StatusCode::INTERNAL_SERVER_ERROR)
-            .body(Body::from("No active game session found"))
-            .unwrap();
+        return Err(ApiError::Conflict("No active game session found".into()));
     }
 
     let device_manager = app_state.device_manager.clone();
+    let backend = app_state.active_backend.as_mut()
+        .ok_or_else(|| ApiError::Conflict("There is no active backend".into()))?;
+    backend.reconnect_async(device_manager).await?;
 
-    match &app_state.backend_type {
-        BackendType::WiVRn | BackendType::Envision => {
-            match app_state.active_backend.as_mut() {
-                Some(backend) => {
-                    match backend.reconnect_async(device_manager).await {
-                        Ok(_) => StatusCode::OK.into_response(),
-                        Err(error) => Response::builder()
-                            .status(StatusCode::OK)
-                            .body(Body::from(error.to_string()))
-                            .unwrap(),
-                    }
-                }
-                None => Response::builder()
-                    .status(StatusCode::OK)
-                    .body(Body::from("There is no active backend"))
-                    .unwrap(),
-            }
-        }
-        _ => todo!("Reloading this backend is not implemented: {:?}", app_state.backend_type),
-    }
-}
\ No newline at end of file
+    Ok(())
+}
@@ -1,52 +1,245 @@
-use axum::body::Body;
-use axum::extract::Path;
+use axum::body::{Body, Bytes};
+use axum::extract::{Path, State};
 use axum::http::{header, StatusCode};
 use axum::Json;
 use axum::response::{IntoResponse, Response};
-use diesel::{QueryDsl, RunQueryDsl, SelectableHelper};
-use crate::models::{establish_connection, Game};
+use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper, SqliteConnection};
+use uuid::Uuid;
+use crate::app_state::AppStateWrapper;
+use crate::cover_art;
+use crate::database::DbPool;
+use crate::models::{Game, GameFields, GamePatch, NewGame};
+use crate::routes::api_error::{ApiError, ApiResult};
 use crate::schema::games::dsl::*;
+use crate::schema::sessions;
+use crate::steam::library_cache::LibraryCache;
 
-pub async fn list_games() -> Json<Vec<Game>> {
-    let connection = &mut establish_connection();
+pub const MAX_COVER_SIZE_BYTES: usize = 16 * 1024 * 1024;
+
+pub async fn list_games(State(app_state): State<AppStateWrapper>) -> ApiResult<Json<Vec<Game>>> {
+    let connection = &mut get_pool(&app_state).await.get()?;
     let results = games
         .select(Game::as_select())
-        .load(connection)
-        .expect("Error loading games");
+        .load(connection)?;
 
-    Json(results)
+    Ok(Json(results))
 }
 
-pub async fn get_game_info(Path(game_id): Path<String>) -> impl IntoResponse {
-    let connection = &mut establish_connection();
-    let result = games
-        .select(Game::as_select())
-        .find(game_id)
-        .load(connection)
-        .expect("Error loading games");
+pub async fn get_game_info(
+    State(app_state): State<AppStateWrapper>,
+    Path(game_id): Path<String>,
+) -> ApiResult<Json<Game>> {
+    let connection = &mut get_pool(&app_state).await.get()?;
+    Ok(Json(load_game(connection, &game_id)?))
+}
+
+pub async fn get_game_cover(
+    State(app_state): State<AppStateWrapper>,
+    Path(game_id): Path<String>,
+) -> ApiResult<Response> {
+    let connection = &mut get_pool(&app_state).await.get()?;
+    let cover_data = load_cover(connection, &game_id)?;
+
+    Ok(image_response(cover_art::mime_type_of(&cover_data), cover_data))
+}
+
+pub async fn get_game_cover_thumbnail(
+    State(app_state): State<AppStateWrapper>,
+    Path(game_id): Path<String>,
+) -> ApiResult<Response> {
+    let connection = &mut get_pool(&app_state).await.get()?;
+    let cover_data = load_cover(connection, &game_id)?;
+
+    let thumbnail = tokio::task::spawn_blocking(move || {
+        cover_art::get_thumbnail(&game_id, &cover_data)
+    }).await??;
+
+    Ok(image_response("image/jpeg", thumbnail.to_vec()))
+}
+
+pub async fn create_game(
+    State(app_state): State<AppStateWrapper>,
+    Json(fields): Json<GameFields>,
+) -> ApiResult<impl IntoResponse> {
+    validate_fields(&app_state, &fields).await?;
+
+    let new_game = NewGame {
+        id: Uuid::new_v4().to_string(),
+        fields,
+    };
+
+    let connection = &mut get_pool(&app_state).await.get()?;
+    diesel::insert_into(games)
+        .values(&new_game)
+        .execute(connection)?;
+
+    Ok((StatusCode::CREATED, Json(load_game(connection, &new_game.id)?)))
+}
+
+pub async fn replace_game(
+    State(app_state): State<AppStateWrapper>,
+    Path(game_id): Path<String>,
+    Json(fields): Json<GameFields>,
+) -> ApiResult<Json<Game>> {
+    let connection = &mut get_pool(&app_state).await.get()?;
+    load_game(connection, &game_id)?;
+
+    save_game_fields(&app_state, connection, game_id, fields).await
+}
+
+pub async fn patch_game(
+    State(app_state): State<AppStateWrapper>,
+    Path(game_id): Path<String>,
+    Json(patch): Json<GamePatch>,
+) -> ApiResult<Json<Game>> {
+    let connection = &mut get_pool(&app_state).await.get()?;
+    let mut fields = GameFields::from(&load_game(connection, &game_id)?);
+    fields.apply_patch(patch);
+
+    save_game_fields(&app_state, connection, game_id, fields).await
+}
+
+pub async fn delete_game(
+    State(app_state): State<AppStateWrapper>,
+    Path(game_id): Path<String>,
+) -> ApiResult<StatusCode> {
+    reject_if_active(&app_state, &game_id).await?;
+
+    let connection = &mut get_pool(&app_state).await.get()?;
+    let deleted = connection.transaction(|connection| {
+        diesel::delete(sessions::table.filter(sessions::game_id.eq(&game_id)))
+            .execute(connection)?;
+        diesel::delete(games.find(&game_id))
+            .execute(connection)
+    })?;
+    cover_art::invalidate_thumbnail(&game_id);
+
+    match deleted {
+        0 => Err(ApiError::NotFound),
+        _ => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+pub async fn upload_game_cover(
+    State(app_state): State<AppStateWrapper>,
+    Path(game_id): Path<String>,
+    body: Bytes,
+) -> ApiResult<Response> {
+    if cover_art::detect_image_format(&body).is_none() {
+        return Ok((StatusCode::UNSUPPORTED_MEDIA_TYPE, "The cover must be a PNG, JPEG or WebP image").into_response());
+    }
+
+    if let Err(error) = image::load_from_memory(&body) {
+        return Err(ApiError::BadRequest(format!("The cover image could not be decoded: {}", error)));
+    }
+
+    let connection = &mut get_pool(&app_state).await.get()?;
+    let updated = diesel::update(games.find(&game_id))
+        .set(cover.eq(Some(body.to_vec())))
+        .execute(connection)?;
+    // Only after the write, so a failed update keeps serving the thumbnail of the stored cover
+    cover_art::invalidate_thumbnail(&game_id);
+
+    match updated {
+        0 => Err(ApiError::NotFound),
+        _ => Ok(StatusCode::NO_CONTENT.into_response()),
+    }
+}
+
+pub async fn delete_game_cover(
+    State(app_state): State<AppStateWrapper>,
+    Path(game_id): Path<String>,
+) -> ApiResult<StatusCode> {
+    let connection = &mut get_pool(&app_state).await.get()?;
+    let updated = diesel::update(games.find(&game_id))
+        .set(cover.eq(None::<Vec<u8>>))
+        .execute(connection)?;
+    cover_art::invalidate_thumbnail(&game_id);
+
+    match updated {
+        0 => Err(ApiError::NotFound),
+        _ => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+async fn save_game_fields(
+    app_state: &AppStateWrapper,
+    connection: &mut SqliteConnection,
+    game_id: String,
+    fields: GameFields,
+) -> ApiResult<Json<Game>> {
+    validate_fields(app_state, &fields).await?;
+    reject_if_active(app_state, &game_id).await?;
+
+    let updated = diesel::update(games.find(&game_id))
+        .set(&fields)
+        .execute(connection)?;
+
+    match updated {
+        0 => Err(ApiError::NotFound),
+        _ => Ok(Json(load_game(connection, &game_id)?)),
+    }
+}
+
+async fn validate_fields(app_state: &AppStateWrapper, fields: &GameFields) -> ApiResult<()> {
+    let app_state = app_state.lock().await;
+    let proton_versions = match &fields.proton_version {
+        Some(_) => app_state.steam_api.get_proton_versions()
+            .map_err(|error| ApiError::Validation(vec![format!("Could not list the installed Proton versions: {}", error)]))?,
+        None => vec![],
+    };
 
-    match result.first() {
-        Some(game) => Json(game).into_response(),
-        None => StatusCode::NOT_FOUND.into_response(),
+    fields.validate(&proton_versions, &app_state.backend_registry).map_err(ApiError::Validation)
+}
+
+/// Games that are currently running must not be modified or removed from under the session
+async fn reject_if_active(app_state: &AppStateWrapper, game_id: &str) -> ApiResult<()> {
+    let app_state = app_state.lock().await;
+    match &app_state.active_game_session {
+        Some(session) if session.game.id == game_id => Err(ApiError::Conflict("This game is currently running".into())),
+        _ => Ok(()),
     }
 }
 
-pub async fn get_game_cover(Path(game_id): Path<String>) -> impl IntoResponse {
-    let connection = &mut establish_connection();
-    let result = games
+async fn get_pool(app_state: &AppStateWrapper) -> DbPool {
+    app_state.lock().await.db_pool.clone()
+}
+
+pub fn load_game(connection: &mut SqliteConnection, game_id: &str) -> ApiResult<Game> {
+    let game = games
         .select(Game::as_select())
         .find(game_id)
-        .load(connection)
-        .expect("Error loading games");
-
-    match result.first().and_then(|g| g.cover.as_ref()) {
-        Some(cover_data) => Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "image/jpg")
-            .header(header::CONTENT_LENGTH, cover_data.len())
-            .body(Body::from(cover_data.clone()))
-            .unwrap()
-            .into_response(),
-        None => StatusCode::NOT_FOUND.into_response(),
-    }
-}
\ No newline at end of file
+        .first(connection)?;
+
+    Ok(game)
+}
+
+/// Returns the stored cover, or fills it in from Steam's local artwork cache for Steam games
+fn load_cover(connection: &mut SqliteConnection, game_id: &str) -> ApiResult<Vec<u8>> {
+    let game = load_game(connection, game_id)?;
+    if let Some(cover_data) = game.cover {
+        return Ok(cover_data);
+    }
+
+    let cover_data = game.steam_app_id
+        .and_then(|app_id| LibraryCache::locate().ok()?.load_cover(app_id as u32))
+        .filter(|cover_data| cover_art::detect_image_format(cover_data).is_some())
+        .ok_or(ApiError::NotFound)?;
+
+    if let Err(error) = diesel::update(games.find(game_id))
+        .set(cover.eq(Some(cover_data.clone())))
+        .execute(connection) {
+        eprintln!("Failed to store the cover of game {}: {:?}", game_id, error);
+    }
+
+    Ok(cover_data)
+}
+
+fn image_response(mime_type: &str, data: Vec<u8>) -> Response {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, mime_type)
+        .header(header::CONTENT_LENGTH, data.len())
+        .body(Body::from(data))
+        .unwrap()
+}
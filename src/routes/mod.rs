@@ -4,4 +4,10 @@ pub mod game_state;
 pub mod sock;
 pub mod debug;
 pub mod device;
-pub mod frontend;
\ No newline at end of file
+pub mod frontend;
+pub mod steam;
+pub mod sessions;
+pub mod api_error;
+pub mod backends;
+pub mod diagnostics;
+pub mod prefixes;
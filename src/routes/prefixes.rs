@@ -0,0 +1,132 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use crate::app_state::{AppState, AppStateWrapper};
+use crate::routes::api_error::{ApiError, ApiResult};
+use crate::routes::games::load_game;
+use crate::schema::games;
+use crate::steam::prefix::{self, PrefixBackup, PrefixInfo};
+use crate::steam::winetricks;
+use diesel::{ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl, SqliteConnection};
+
+pub async fn get_prefix_info(
+    State(app_state): State<AppStateWrapper>,
+    Path(game_id): Path<String>,
+) -> ApiResult<Json<PrefixInfo>> {
+    let prefix_id = load_prefix_id(&app_state, &game_id).await?;
+    let info = tokio::task::spawn_blocking(move || prefix::inspect(&prefix_id)).await??;
+
+    Ok(Json(info))
+}
+
+pub async fn reset_prefix(
+    State(app_state): State<AppStateWrapper>,
+    Path(game_id): Path<String>,
+) -> ApiResult<StatusCode> {
+    with_exclusive_prefix(&app_state, &game_id, |prefix_id, connection| {
+        prefix::reset(prefix_id)?;
+        winetricks::forget_installed_verbs(connection, prefix_id)?;
+
+        Ok(())
+    }).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_prefix_backups(
+    State(app_state): State<AppStateWrapper>,
+    Path(game_id): Path<String>,
+) -> ApiResult<Json<Vec<PrefixBackup>>> {
+    let prefix_id = load_prefix_id(&app_state, &game_id).await?;
+
+    Ok(Json(prefix::list_backups(&prefix_id)?))
+}
+
+pub async fn create_prefix_backup(
+    State(app_state): State<AppStateWrapper>,
+    Path(game_id): Path<String>,
+) -> ApiResult<(StatusCode, Json<PrefixBackup>)> {
+    // A running game would keep writing to the prefix while it is being packed
+    let backup = with_exclusive_prefix(&app_state, &game_id, |prefix_id, _| {
+        prefix::backup(prefix_id).map_err(|error| ApiError::Conflict(error.to_string()))
+    }).await?;
+
+    Ok((StatusCode::CREATED, Json(backup)))
+}
+
+pub async fn restore_prefix_backup(
+    State(app_state): State<AppStateWrapper>,
+    Path((game_id, file_name)): Path<(String, String)>,
+) -> ApiResult<StatusCode> {
+    with_exclusive_prefix(&app_state, &game_id, move |prefix_id, connection| {
+        if !prefix::list_backups(prefix_id)?.iter().any(|backup| backup.file_name == file_name) {
+            return Err(ApiError::NotFound);
+        }
+        prefix::restore(prefix_id, &file_name)?;
+        // The restored prefix may not have every verb the current one had
+        winetricks::forget_installed_verbs(connection, prefix_id)?;
+
+        Ok(())
+    }).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Assigns the prefix id if the game has never been launched
+async fn load_prefix_id(app_state: &AppStateWrapper, game_id: &str) -> ApiResult<String> {
+    let state = app_state.lock().await;
+    let connection = &mut state.db_pool.get()?;
+    let game = load_game(connection, game_id)?;
+
+    Ok(prefix::ensure_prefix_id(connection, &game)?)
+}
+
+/// Runs `operation` on a blocking thread while the game's prefix is marked as busy, so no game
+/// using it can be launched until it is done. Rejected while such a game is running or being launched.
+async fn with_exclusive_prefix<T, F>(app_state: &AppStateWrapper, game_id: &str, operation: F) -> ApiResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&str, &mut SqliteConnection) -> ApiResult<T> + Send + 'static,
+{
+    let (prefix_id, pool) = {
+        let mut state = app_state.lock().await;
+        let pool = state.db_pool.clone();
+        let connection = &mut pool.get()?;
+        let game = load_game(connection, game_id)?;
+        let prefix_id = prefix::ensure_prefix_id(connection, &game)?;
+
+        if is_prefix_in_use(&state, connection, &prefix_id)? {
+            return Err(ApiError::Conflict("The prefix cannot be changed while a game using it is running".into()));
+        }
+        if !state.busy_prefixes.insert(prefix_id.clone()) {
+            return Err(ApiError::Conflict("Another operation on this prefix is in progress".into()));
+        }
+
+        (prefix_id, pool)
+    };
+
+    let operation_prefix_id = prefix_id.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let connection = &mut pool.get()?;
+        operation(&operation_prefix_id, connection)
+    }).await;
+    app_state.lock().await.busy_prefixes.remove(&prefix_id);
+
+    result?
+}
+
+/// Games get their prefix id assigned before they are launched or queued, see `AppState::start_launch`
+fn is_prefix_in_use(state: &AppState, connection: &mut SqliteConnection, prefix_id: &str) -> QueryResult<bool> {
+    let game_ids = state.active_game_session.iter().map(|session| &session.game.id)
+        .chain(state.active_launch.iter().map(|launch| &launch.progress.game_id))
+        .chain(state.launch_queue.iter().map(|launch| &launch.game_id))
+        .collect::<Vec<_>>();
+
+    let count = games::table
+        .filter(games::id.eq_any(game_ids))
+        .filter(games::prefix_id.eq(prefix_id))
+        .count()
+        .get_result::<i64>(connection)?;
+
+    Ok(count > 0)
+}
@@ -0,0 +1,117 @@
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper, SqliteConnection};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::app_state::AppStateWrapper;
+use crate::models::Session;
+use crate::routes::api_error::ApiResult;
+use crate::schema::sessions::dsl::*;
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct PageQuery {
+    page: Option<i64>,
+    page_size: Option<i64>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct GameStats {
+    game_id: String,
+    session_count: i64,
+    total_playtime_sec: i64,
+    last_played_epoch: Option<i64>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPage {
+    items: Vec<Session>,
+    page: i64,
+    page_size: i64,
+    total_count: i64,
+}
+
+pub async fn get_game_stats(
+    State(app_state): State<AppStateWrapper>,
+    Path(target_game_id): Path<String>,
+) -> ApiResult<Json<GameStats>> {
+    let pool = app_state.lock().await.db_pool.clone();
+    let connection = &mut pool.get()?;
+    let durations = sessions
+        .filter(game_id.eq(&target_game_id))
+        .select((start_time, end_time))
+        .load::<(i64, i64)>(connection)?;
+
+    Ok(Json(GameStats {
+        game_id: target_game_id,
+        session_count: durations.len() as i64,
+        total_playtime_sec: durations.iter()
+            .map(|(start, end)| (end - start).max(0))
+            .sum(),
+        last_played_epoch: durations.iter()
+            .map(|(_, end)| *end)
+            .max(),
+    }))
+}
+
+pub async fn list_game_sessions(
+    State(app_state): State<AppStateWrapper>,
+    Path(target_game_id): Path<String>,
+    Query(query): Query<PageQuery>,
+) -> ApiResult<Json<SessionPage>> {
+    let pool = app_state.lock().await.db_pool.clone();
+    let connection = &mut pool.get()?;
+    load_session_page(connection, Some(target_game_id), false, query)
+}
+
+pub async fn list_all_sessions(
+    State(app_state): State<AppStateWrapper>,
+    Query(query): Query<PageQuery>,
+) -> ApiResult<Json<SessionPage>> {
+    let pool = app_state.lock().await.db_pool.clone();
+    let connection = &mut pool.get()?;
+    load_session_page(connection, None, false, query)
+}
+
+/// Sessions whose game has crashed, each with its diagnostics bundle when one could be created
+pub async fn list_crashed_sessions(
+    State(app_state): State<AppStateWrapper>,
+    Query(query): Query<PageQuery>,
+) -> ApiResult<Json<SessionPage>> {
+    let pool = app_state.lock().await.db_pool.clone();
+    let connection = &mut pool.get()?;
+    load_session_page(connection, None, true, query)
+}
+
+fn load_session_page(connection: &mut SqliteConnection, target_game_id: Option<String>, crashes_only: bool, query: PageQuery) -> ApiResult<Json<SessionPage>> {
+    let page = query.page.unwrap_or(0).max(0);
+    let page_size = query.page_size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    let mut count_query = sessions.count().into_boxed();
+    let mut items_query = sessions
+        .select(Session::as_select())
+        .order(start_time.desc())
+        .limit(page_size)
+        .offset(page * page_size)
+        .into_boxed();
+
+    if let Some(target_game_id) = target_game_id {
+        count_query = count_query.filter(game_id.eq(target_game_id.clone()));
+        items_query = items_query.filter(game_id.eq(target_game_id));
+    }
+    if crashes_only {
+        count_query = count_query.filter(crash_kind.is_not_null());
+        items_query = items_query.filter(crash_kind.is_not_null());
+    }
+
+    let total_count = count_query.get_result::<i64>(connection)?;
+    let items = items_query.load(connection)?;
+
+    Ok(Json(SessionPage { items, page, page_size, total_count }))
+}
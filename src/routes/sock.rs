@@ -4,16 +4,23 @@ use axum::extract::ws::WebSocket;
 use axum::response::Response;
 use futures_util::SinkExt;
 use futures_util::stream::StreamExt;
+use tokio::sync::mpsc;
 use crate::app_state::AppStateWrapper;
+use crate::events::{ClientCommand, ClientMessage, CommandAck, CommandError, HelloInfo, ServerEvent, PROTOCOL_VERSION};
+use crate::routes::api_error::ApiResult;
+use crate::routes::{audio, game_state};
+use crate::routes::game_state::LaunchMode;
+use crate::steam::process_tree::DEFAULT_GRACE_PERIOD;
 
 pub async fn sock_state_handler(ws: WebSocketUpgrade, State(state): State<AppStateWrapper>) -> Response {
     ws.on_upgrade(|socket| sock_state(socket, state))
 }
 
 async fn sock_state(socket: WebSocket, state: AppStateWrapper) {
-    let (mut data_rx, mut stop_rx) = {
+    // Subscribe before taking the snapshot, so no event can fall between the two
+    let (mut data_rx, mut stop_rx, snapshot) = {
         let state = state.lock().await;
-        let result = (state.sock_tx.subscribe(), state.socket_stop_tx.subscribe());
+        let result = (state.sock_tx.subscribe(), state.socket_stop_tx.subscribe(), state.snapshot_async().await);
         drop(state);
 
         result
@@ -21,6 +28,15 @@ async fn sock_state(socket: WebSocket, state: AppStateWrapper) {
 
     let (mut sender, mut receiver) = socket.split();
 
+    for event in [ServerEvent::Hello(HelloInfo { protocol_version: PROTOCOL_VERSION }), ServerEvent::Snapshot(Box::new(snapshot))] {
+        if sender.send(to_message(&event)).await.is_err() {
+            return;
+        }
+    }
+
+    // Replies only go to the client that sent the command, not to every subscriber
+    let (reply_tx, mut reply_rx) = mpsc::unbounded_channel::<ServerEvent>();
+
     let send_task = tokio::spawn(async move {
         loop {
             tokio::select! {
@@ -29,16 +45,27 @@ async fn sock_state(socket: WebSocket, state: AppStateWrapper) {
                     break;
                 }
                 message = receiver.next() => {
-                    if let Some(Ok(Message::Ping(ping))) = message {
-                        _ = sender.send(Message::Pong(ping)).await;
-                    } else if message.is_none_or(|m| m.is_err()) {
-                        return
+                    match message {
+                        Some(Ok(Message::Ping(ping))) => {
+                            _ = sender.send(Message::Pong(ping)).await;
+                        }
+                        Some(Ok(Message::Text(text))) => {
+                            // Commands may take a while (e.g. starting a backend), so they must not block the events
+                            tokio::spawn(handle_client_message(state.clone(), text.to_string(), reply_tx.clone()));
+                        }
+                        Some(Ok(_)) => {}
+                        None | Some(Err(_)) => return,
+                    }
+                }
+                Some(reply) = reply_rx.recv() => {
+                    if sender.send(to_message(&reply)).await.is_err() {
+                        break; // Client disconnected
                     }
                 }
                 message_result = data_rx.recv() => {
                     match message_result {
-                        Ok(message) => {
-                            if sender.send(Message::Text(message.into())).await.is_err() {
+                        Ok(event) => {
+                            if sender.send(to_message(&event)).await.is_err() {
                                 break; // Client disconnected
                             }
                         },
@@ -56,4 +83,57 @@ async fn sock_state(socket: WebSocket, state: AppStateWrapper) {
     tokio::select! {
         _ = send_task => {},
     }
-}
\ No newline at end of file
+}
+
+async fn handle_client_message(state: AppStateWrapper, text: String, reply_tx: mpsc::UnboundedSender<ServerEvent>) {
+    let message = match serde_json::from_str::<ClientMessage>(&text) {
+        Ok(message) => message,
+        Err(error) => {
+            // Still try to correlate the error with the request, if the id is readable
+            let request_id = serde_json::from_str::<serde_json::Value>(&text).ok()
+                .and_then(|value| value.get("requestId")?.as_str().map(str::to_string))
+                .unwrap_or_default();
+            _ = reply_tx.send(ServerEvent::CommandError(CommandError {
+                request_id,
+                message: format!("Invalid command: {}", error),
+            }));
+            return;
+        }
+    };
+
+    println!("[Axum/SOCK] Handling command {:?}", message.command);
+    let reply = match execute_command(&state, message.command).await {
+        Ok(launch_id) => ServerEvent::CommandAck(CommandAck { request_id: message.request_id, launch_id }),
+        Err(error) => ServerEvent::CommandError(CommandError {
+            request_id: message.request_id,
+            message: error.to_string(),
+        }),
+    };
+
+    _ = reply_tx.send(reply);
+}
+
+/// Returns the id of the launch the command has started, if any
+async fn execute_command(app_state: &AppStateWrapper, command: ClientCommand) -> ApiResult<Option<String>> {
+    let result = match command {
+        ClientCommand::LaunchGame { game_id, queue } => {
+            let mode = match queue {
+                true => LaunchMode::Queue,
+                false => LaunchMode::Start,
+            };
+            return game_state::launch_game(app_state, &game_id, mode).await.map(Some);
+        }
+        ClientCommand::SwitchGame { game_id } => return game_state::launch_game(app_state, &game_id, LaunchMode::Switch).await.map(Some),
+        ClientCommand::CancelLaunch { launch_id } => game_state::cancel_launch(&mut *app_state.lock().await, &launch_id),
+        ClientCommand::KillGame => game_state::kill_game(app_state, DEFAULT_GRACE_PERIOD).await.map(|_| ()),
+        ClientCommand::SetVolume { device_id, volume, muted } => audio::set_device_volume(&*app_state.lock().await, device_id, volume, muted),
+        ClientCommand::SetDefaultDevice { endpoint, device_id } => audio::set_default_device(&*app_state.lock().await, endpoint, device_id),
+        ClientCommand::ReloadBackend => game_state::reload_active_backend(&mut *app_state.lock().await).await,
+    };
+
+    result.map(|_| None)
+}
+
+fn to_message(event: &ServerEvent) -> Message {
+    Message::Text(serde_json::to_string(event).unwrap().into())
+}
@@ -0,0 +1,44 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use axum::response::IntoResponse;
+use crate::app_state::AppStateWrapper;
+use crate::routes::api_error::{ApiError, ApiResult};
+use crate::steam::library_import::{find_import_candidates, ImportCandidate, ImportRequest};
+use crate::steam::steam_interface::{SteamInterface, SteamLaunchConfig};
+
+pub async fn list_importable_apps(State(app_state): State<AppStateWrapper>) -> ApiResult<Json<Vec<ImportCandidate>>> {
+    let pool = app_state.lock().await.db_pool.clone();
+    let candidates = tokio::task::spawn_blocking(move || {
+        let steam_api = SteamInterface::new();
+        let connection = &mut pool.get()?;
+        find_import_candidates(&steam_api, connection)
+    }).await??;
+
+    Ok(Json(candidates))
+}
+
+pub async fn list_launch_configs(Path(app_id): Path<u32>) -> ApiResult<Json<Vec<SteamLaunchConfig>>> {
+    let launch_configs = tokio::task::spawn_blocking(move || SteamInterface::new().get_launch_configs(app_id)).await??
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(Json(launch_configs))
+}
+
+pub async fn get_import_status(State(app_state): State<AppStateWrapper>) -> impl IntoResponse {
+    let app_state = app_state.lock().await;
+    Json(app_state.steam_import.get_status())
+}
+
+pub async fn start_import(
+    State(app_state): State<AppStateWrapper>,
+    request: Option<Json<ImportRequest>>,
+) -> impl IntoResponse {
+    let app_state = app_state.lock().await;
+    let request = request.map(|Json(r)| r).unwrap_or_default();
+
+    match app_state.steam_import.start(request, app_state.db_pool.clone(), app_state.sock_tx.clone()) {
+        true => (StatusCode::ACCEPTED, Json(app_state.steam_import.get_status())).into_response(),
+        false => (StatusCode::CONFLICT, "A Steam library import is already running").into_response(),
+    }
+}
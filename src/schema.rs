@@ -12,5 +12,66 @@ diesel::table! {
         command_line -> Nullable<Text>,
         proton_version -> Nullable<Text>,
         use_overlay -> Bool,
+        use_systemd_scope -> Bool,
+        prefix_id -> Nullable<Text>,
+        winetricks_verbs -> Text,
+        launch_options -> Text,
+        steam_launch_config -> Nullable<Text>,
     }
 }
+
+diesel::table! {
+    installed_verbs (prefix_id, verb) {
+        prefix_id -> Text,
+        verb -> Text,
+        installed_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    known_devices (serial) {
+        serial -> Text,
+        manufacturer -> Text,
+        product_name -> Text,
+        network_address -> Text,
+    }
+}
+
+diesel::table! {
+    launch_requests (token) {
+        token -> Text,
+        launch_id -> Text,
+        game_id -> Text,
+        outcome -> Nullable<Text>,
+        error -> Nullable<Text>,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    sessions (id) {
+        id -> Integer,
+        game_id -> Text,
+        start_time -> BigInt,
+        end_time -> BigInt,
+        exit_code -> Nullable<Integer>,
+        exit_signal -> Nullable<Integer>,
+        vr_backend -> Text,
+        device_serial -> Nullable<Text>,
+        log_archive -> Nullable<Text>,
+        cpu_time_ms -> Nullable<BigInt>,
+        peak_memory_bytes -> Nullable<BigInt>,
+        crash_kind -> Nullable<Text>,
+        diagnostics_bundle -> Nullable<Text>,
+    }
+}
+
+diesel::joinable!(sessions -> games (game_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    games,
+    installed_verbs,
+    known_devices,
+    launch_requests,
+    sessions,
+);
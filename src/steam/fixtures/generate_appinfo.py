@@ -0,0 +1,130 @@
+#!/usr/bin/env python3
+"""Builds appinfo.vdf, the fixture of the Steam launch config tests.
+
+The fixture is synthetic: the apps are made up, and the hashes, tokens and timestamps are
+placeholders, since the parser does not check them. It is not an excerpt of a real appcache
+because appinfo.vdf is only written by a logged-in Steam client, and its entries are Valve's
+metadata, which is not ours to redistribute. Real files are covered by the ignored
+`test_parse_local_appinfo` test instead, run it with `cargo test -- --ignored` on a machine
+with Steam installed.
+
+The file uses the binary layout of Steam's appcache/appinfo.vdf (format version 29): a header,
+one entry per app holding a binary KeyValues object whose keys are indices into a string table,
+and the string table itself at the end of the file.
+
+Usage: python3 generate_appinfo.py > appinfo.vdf
+"""
+
+import struct
+import sys
+
+MAGIC = 0x07564429
+UNIVERSE_PUBLIC = 1
+INFO_STATE = 2
+LAST_UPDATED = 1700000000
+CHANGE_NUMBER = 1
+
+TYPE_OBJECT = 0x00
+TYPE_STRING = 0x01
+TYPE_INT32 = 0x02
+TYPE_END = 0x08
+
+APPS = [
+    (1001, {
+        "appinfo": {
+            "appid": 1001,
+            "common": {"name": "VR Sandbox", "type": "Game", "openvrsupport": "1"},
+            "config": {
+                "installdir": "VR Sandbox",
+                "launch": {
+                    "0": {
+                        "executable": "Sandbox.exe",
+                        "arguments": "-window-mode exclusive",
+                        "type": "default",
+                        "description": "Play in desktop mode",
+                        "config": {"oslist": "windows"},
+                    },
+                    "1": {
+                        "executable": "Sandbox.exe",
+                        "arguments": "-vr  -screen-fullscreen 0 ",
+                        "type": "vr",
+                        "description": "Launch in VR",
+                        "config": {"oslist": "windows"},
+                    },
+                    "2": {
+                        "executable": "SandboxBeta.exe",
+                        "arguments": "-vr",
+                        "type": "vr",
+                        "description": "Launch the beta in VR",
+                        "config": {"oslist": "windows", "betakey": "experimental"},
+                    },
+                },
+            },
+        },
+    }),
+    (1002, {
+        "appinfo": {
+            "appid": 1002,
+            "common": {"name": "Flight Club", "type": "Game", "openvrsupport": "1"},
+            "config": {
+                "installdir": "Flight Club",
+                "launch": {
+                    "0": {
+                        "executable": "bin\\win64\\FlightClub.exe",
+                        "arguments": '-dataPath "C:\\Program Files\\Flight Club\\data\\\\" +exec "autoexec vr.cfg" -title ""',
+                        "type": "vr",
+                        "config": {"oslist": "windows"},
+                    },
+                    "1": {
+                        "executable": "bin/linux64/flightclub.sh",
+                        "arguments": '--log-file "/tmp/flight club.log"   --vulkan',
+                        "config": {"oslist": "linux"},
+                    },
+                },
+            },
+        },
+    }),
+]
+
+
+def encode_object(obj, strings):
+    data = bytearray()
+    for key, value in obj.items():
+        if key not in strings:
+            strings.append(key)
+        key_index = struct.pack("<I", strings.index(key))
+
+        if isinstance(value, dict):
+            data += bytes([TYPE_OBJECT]) + key_index + encode_object(value, strings)
+        elif isinstance(value, int):
+            data += bytes([TYPE_INT32]) + key_index + struct.pack("<i", value)
+        else:
+            data += bytes([TYPE_STRING]) + key_index + value.encode() + b"\0"
+
+    return data + bytes([TYPE_END])
+
+
+def encode_entry(app_id, obj, strings):
+    body = struct.pack("<IIQ", INFO_STATE, LAST_UPDATED, 0)
+    body += bytes(20)
+    body += struct.pack("<I", CHANGE_NUMBER)
+    body += bytes(20)
+    body += encode_object(obj, strings)
+
+    return struct.pack("<II", app_id, len(body)) + body
+
+
+def main():
+    strings = []
+    entries = b"".join(encode_entry(app_id, obj, strings) for app_id, obj in APPS)
+    entries += struct.pack("<I", 0)
+
+    header_size = 16
+    header = struct.pack("<IIQ", MAGIC, UNIVERSE_PUBLIC, header_size + len(entries))
+    string_table = struct.pack("<I", len(strings)) + b"".join(s.encode() + b"\0" for s in strings)
+
+    sys.stdout.buffer.write(header + entries + string_table)
+
+
+if __name__ == "__main__":
+    main()
@@ -2,15 +2,17 @@ use crate::steam::launch_modifiers::LaunchModifier;
 use crate::steam::steam_interface::{ProtonVersion, SteamApp};
 use anyhow::ensure;
 use std::fs;
-use std::hash::{DefaultHasher, Hash, Hasher};
 use tokio::process;
 use vdf_reader::entry::Table;
 
-pub struct SteamLaunchModifier;
+pub struct SteamLaunchModifier {
+    prefix_id: String,
+}
 
 impl SteamLaunchModifier {
-    pub fn new() -> Self {
-        Self {}
+    /// `prefix_id` names the game's folder in `compatdata` and `shadercache`, see `prefix::ensure_prefix_id`
+    pub fn new(prefix_id: String) -> Self {
+        Self { prefix_id }
     }
 }
 
@@ -23,13 +25,10 @@ const LOGINUSERS: &str = "config/loginusers.vdf";
 impl LaunchModifier for SteamLaunchModifier {
     fn apply(&self, command: &mut process::Command, app: &SteamApp, compat_version: Option<&ProtonVersion>) -> anyhow::Result<()> {
         // Steam IDs
-        let assigned_id = match app.steam_id {
-            0 => generate_20_digit_code(&app.executable),
-            _ => app.steam_id.to_string()
-        };
+        let assigned_id = &self.prefix_id;
         command.env("SteamAppId", app.steam_id.to_string());
-        command.env("SteamGameId", &assigned_id);
-        command.env("SteamOverlayGameId", &assigned_id);
+        command.env("SteamGameId", assigned_id);
+        command.env("SteamOverlayGameId", assigned_id);
 
         // Steam Basic
         command.env("SteamUser", get_user_name()?);
@@ -39,7 +38,7 @@ impl LaunchModifier for SteamLaunchModifier {
         let steam_home = steamlocate::SteamDir::locate()?;
         command.env("STEAM_COMPAT_APP_ID", app.steam_id.to_string());
         command.env("STEAM_COMPAT_CLIENT_INSTALL_PATH", steam_home.path());
-        command.env("STEAM_COMPAT_DATA_PATH", steam_home.path().join(COMPATDATA).join(assigned_id.to_string()));
+        command.env("STEAM_COMPAT_DATA_PATH", steam_home.path().join(COMPATDATA).join(assigned_id));
         command.env("STEAM_COMPAT_FLAGS", "search-cwd"); // only present on Steam games
         command.env("STEAM_COMPAT_LIBRARY_PATHS", steam_home.path().join(STEAMAPPS));
         command.env("STEAM_COMPAT_INSTALL_PATH", &app.app_folder);
@@ -50,9 +49,9 @@ impl LaunchModifier for SteamLaunchModifier {
         command.env("AMD_VK_PIPELINE_CACHE_FILENAME", "steamapp_shader_cache");
         command.env("STEAM_BASE_FOLDER", steam_home.path());
         command.env("STEAM_CLIENT_CONFIG_FILE", steam_home.path().join("steam.cfg"));
-        command.env("AMD_VK_PIPELINE_CACHE_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id.to_string()).join("AMDv1"));
+        command.env("AMD_VK_PIPELINE_CACHE_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id).join("AMDv1"));
         command.env("AMD_VK_USE_PIPELINE_CACHE", "1");
-        command.env("DXVK_STATE_CACHE_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id.to_string()).join("DXVK_state_cache"));
+        command.env("DXVK_STATE_CACHE_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id).join("DXVK_state_cache"));
         command.env("FOSSILIZE_APPLICATION_INFO_FILTER_PATH", steam_home.path().join("fossilize_engine_filters.json"));
         command.env("SDL_GAMECONTROLLER_ALLOW_STEAM_VIRTUAL_GAMEPAD", "1");
         command.env("SDL_JOYSTICK_HIDAPI_STEAMXBOX", "0");
@@ -66,13 +65,13 @@ impl LaunchModifier for SteamLaunchModifier {
         command.env("__GL_SHADER_DISK_CACHE_SKIP_CLEANUP", "1");
         command.env("__GL_SHADER_DISK_CACHE_APP_NAME", "steamapp_shader_cache");
         command.env("__GL_SHADER_DISK_CACHE_READ_ONLY_APP_NAME", "steam_shader_cache;steamapp_merged_shader_cache");
-        command.env("__GL_SHADER_DISK_CACHE_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id.to_string()).join("fozmediav1"));
-        command.env("STEAM_COMPAT_MEDIA_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id.to_string()).join("fozmediav1"));
-        command.env("STEAM_FOSSILIZE_DUMP_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id.to_string()).join("fozpipelinesv6/steamapprun_pipeline_cache"));
-        command.env("STEAM_COMPAT_SHADER_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id.to_string()));
-        command.env("MESA_GLSL_CACHE_DIR", steam_home.path().join(SHADERCACHE).join(assigned_id.to_string()));
-        command.env("MESA_SHADER_CACHE_DIR", steam_home.path().join(SHADERCACHE).join(assigned_id.to_string()));
-        command.env("STEAM_COMPAT_TRANSCODED_MEDIA_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id.to_string()));
+        command.env("__GL_SHADER_DISK_CACHE_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id).join("fozmediav1"));
+        command.env("STEAM_COMPAT_MEDIA_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id).join("fozmediav1"));
+        command.env("STEAM_FOSSILIZE_DUMP_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id).join("fozpipelinesv6/steamapprun_pipeline_cache"));
+        command.env("STEAM_COMPAT_SHADER_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id));
+        command.env("MESA_GLSL_CACHE_DIR", steam_home.path().join(SHADERCACHE).join(assigned_id));
+        command.env("MESA_SHADER_CACHE_DIR", steam_home.path().join(SHADERCACHE).join(assigned_id));
+        command.env("STEAM_COMPAT_TRANSCODED_MEDIA_PATH", steam_home.path().join(SHADERCACHE).join(assigned_id));
         command.env("STEAM_COMPAT_MOUNTS", vec![
             steam_home.path().join(COMMON).join("SteamLinuxRuntime_sniper").to_str().unwrap(),
             //     steam_home.path().join(COMMON).join("Steamworks Shared").to_str().unwrap(), // TODO: Only for Steam games ???
@@ -90,24 +89,16 @@ impl LaunchModifier for SteamLaunchModifier {
         //command.env("STEAM_RUNTIME_LIBRARY_PATH", todo!("List of Steam's bin library folders"));
         command.env("WINEDLLOVERRIDES", "winhttp=n,b"); // only BSManager does this
 
-        fs::create_dir_all(steam_home.path().join(COMPATDATA).join(assigned_id.to_string()))?;
-        fs::create_dir_all(steam_home.path().join(SHADERCACHE).join(assigned_id.to_string()).join("fozmediav1"))?;
-        fs::create_dir_all(steam_home.path().join(SHADERCACHE).join(assigned_id.to_string()).join("fozpipelinesv6"))?;
-        fs::create_dir_all(steam_home.path().join(SHADERCACHE).join(assigned_id.to_string()).join("DXVK_state_cache"))?;
-        fs::create_dir_all(steam_home.path().join(SHADERCACHE).join(assigned_id.to_string()).join("AMDv1"))?;
+        fs::create_dir_all(steam_home.path().join(COMPATDATA).join(assigned_id))?;
+        fs::create_dir_all(steam_home.path().join(SHADERCACHE).join(assigned_id).join("fozmediav1"))?;
+        fs::create_dir_all(steam_home.path().join(SHADERCACHE).join(assigned_id).join("fozpipelinesv6"))?;
+        fs::create_dir_all(steam_home.path().join(SHADERCACHE).join(assigned_id).join("DXVK_state_cache"))?;
+        fs::create_dir_all(steam_home.path().join(SHADERCACHE).join(assigned_id).join("AMDv1"))?;
 
         Ok(())
     }
 }
 
-fn generate_20_digit_code(seed: &str) -> String {
-    let mut hasher = DefaultHasher::new();
-    seed.hash(&mut hasher);
-    let hash = hasher.finish();
-    let hash_str = format!("{:020}", hash);
-    hash_str.chars().take(20).collect()
-}
-
 pub fn get_user_name() -> anyhow::Result<String> {
     let users_file = steamlocate::SteamDir::locate()?.path().join(LOGINUSERS);
     ensure!(users_file.exists(), format!("The user database file doesn't exist: {}", users_file.display()));
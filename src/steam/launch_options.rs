@@ -0,0 +1,122 @@
+use crate::command_parser::tokenize_command;
+use std::process::Stdio;
+use tokio::process;
+
+const COMMAND_PLACEHOLDER: &str = "%command%";
+
+/// A game's launch options, which work like Steam's: `VAR=value` prefixes are set in the
+/// environment, anything before `%command%` wraps the game and anything after it is appended
+/// to its arguments. Without `%command%` every token is an extra argument.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LaunchOptions {
+    pub env_vars: Vec<(String, String)>,
+    pub wrapper: Vec<String>,
+    pub extra_arguments: Vec<String>,
+}
+
+impl LaunchOptions {
+    pub fn parse(options: &str) -> Result<Self, String> {
+        let tokens = tokenize_command(options)?;
+        let placeholders = tokens.iter().filter(|token| *token == COMMAND_PLACEHOLDER).count();
+        if placeholders > 1 {
+            return Err(format!("{} can only be used once", COMMAND_PLACEHOLDER));
+        }
+        if placeholders == 0 {
+            return Ok(Self { extra_arguments: tokens, ..Default::default() });
+        }
+
+        let mut tokens = tokens.into_iter().peekable();
+        let mut env_vars = Vec::new();
+        while let Some((key, value)) = tokens.peek().and_then(|token| token.split_once('='))
+            && is_variable_name(key) {
+            env_vars.push((key.to_string(), value.to_string()));
+            tokens.next();
+        }
+
+        let wrapper = tokens.by_ref().take_while(|token| token != COMMAND_PLACEHOLDER).collect();
+
+        Ok(Self {
+            env_vars,
+            wrapper,
+            extra_arguments: tokens.collect(),
+        })
+    }
+
+    /// Applies the options to the fully prepared `command`, so a wrapper also wraps Proton.
+    /// The output is piped, like for every other game command.
+    pub fn apply(&self, mut command: process::Command) -> process::Command {
+        command.args(&self.extra_arguments);
+        for (key, value) in &self.env_vars {
+            command.env(key, value);
+        }
+
+        let Some((wrapper, wrapper_args)) = self.wrapper.split_first() else {
+            return command;
+        };
+
+        let command = command.as_std();
+        let mut wrapped_command = process::Command::new(wrapper);
+        wrapped_command.args(wrapper_args);
+        wrapped_command.arg(command.get_program());
+        wrapped_command.args(command.get_args());
+
+        for (key, value) in command.get_envs() {
+            match value {
+                Some(value) => wrapped_command.env(key, value),
+                None => wrapped_command.env_remove(key),
+            };
+        }
+        if let Some(current_dir) = command.get_current_dir() {
+            wrapped_command.current_dir(current_dir);
+        }
+
+        wrapped_command.stdout(Stdio::piped());
+        wrapped_command.stderr(Stdio::piped());
+
+        wrapped_command
+    }
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_launch_options() {
+        let options = LaunchOptions::parse("DXVK_HUD=fps PROTON_LOG=1 gamemoderun mangohud %command% -vr \"--config=a b\"").unwrap();
+        assert_eq!(options.env_vars, [("DXVK_HUD".to_string(), "fps".to_string()), ("PROTON_LOG".to_string(), "1".to_string())]);
+        assert_eq!(options.wrapper, ["gamemoderun", "mangohud"]);
+        assert_eq!(options.extra_arguments, ["-vr", "--config=a b"]);
+
+        let options = LaunchOptions::parse("-novid --width=1920").unwrap();
+        assert!(options.env_vars.is_empty() && options.wrapper.is_empty());
+        assert_eq!(options.extra_arguments, ["-novid", "--width=1920"]);
+
+        assert_eq!(LaunchOptions::parse("").unwrap(), LaunchOptions::default());
+        assert!(LaunchOptions::parse("%command% %command%").is_err());
+        assert!(LaunchOptions::parse("taskset -c \"0-3 %command%").is_err());
+    }
+
+    #[test]
+    fn test_apply_wrapper() {
+        let options = LaunchOptions::parse("MANGOHUD=1 taskset -c 0-3 %command% -vr").unwrap();
+        let mut command = process::Command::new("python3");
+        command.args(["proton", "run", "game.exe"]);
+        command.env("SVRL_TOKEN", "token");
+        command.current_dir("/tmp");
+
+        let command = options.apply(command);
+        let command = command.as_std();
+        assert_eq!(command.get_program(), "taskset");
+        assert_eq!(command.get_args().collect::<Vec<_>>(), ["-c", "0-3", "python3", "proton", "run", "game.exe", "-vr"]);
+        assert!(command.get_envs().any(|(key, value)| key == "MANGOHUD" && value == Some("1".as_ref())));
+        assert!(command.get_envs().any(|(key, _)| key == "SVRL_TOKEN"));
+        assert_eq!(command.get_current_dir(), Some("/tmp".as_ref()));
+    }
+}
@@ -1,17 +1,27 @@
-use std::path::Path;
-use std::process::Stdio;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use crate::diagnostics::LaunchEnvironment;
 use crate::logging::log_channel::LogChannel;
 use crate::steam::launch_modifiers::LaunchModifier;
+use crate::steam::launch_options::LaunchOptions;
+use crate::steam::process_tree::{self, GameProcessTree, TrackedProcesses};
+use crate::steam::systemd_scope::{self, ScopeUsage};
 use crate::steam::steam_interface::{ProtonLaunchInfo, SteamApp};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use anyhow::bail;
 use tokio::process;
-use tokio::sync::broadcast::Sender;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 use tokio::task::JoinHandle;
+use tokio::time::Instant;
 use uuid::Uuid;
 use crate::app_state::AppStateWrapper;
 
+const USAGE_SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the game's process tree is walked, a process that is orphaned and not seen within
+/// this time is only found by its environment
+const TRACKING_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct CompatLauncher {
     app_state: Arc<RwLock<Option<AppStateWrapper>>>,
 }
@@ -22,6 +32,13 @@ pub struct ProcessHandle {
     pid: u32,
     process_token: Uuid,
     wait_handle: Option<JoinHandle<()>>,
+    exit_rx: watch::Receiver<Option<ExitStatus>>,
+    tracked: TrackedProcesses,
+    /// The Proton prefix and its `wineserver`, see [`GameProcessTree`]
+    wine_prefix: Option<(PathBuf, PathBuf)>,
+    scope_unit: Option<String>,
+    resource_usage: Arc<Mutex<ScopeUsage>>,
+    launch_environment: LaunchEnvironment,
 }
 
 impl ProcessHandle {
@@ -33,6 +50,27 @@ impl ProcessHandle {
         self.pid
     }
 
+    /// Everything needed to stop the game without holding on to the session
+    pub fn process_tree(&self) -> GameProcessTree {
+        GameProcessTree {
+            pgid: self.pid,
+            process_token: self.process_token,
+            tracked: self.tracked.clone(),
+            exit_rx: self.exit_rx.clone(),
+            wine_prefix: self.wine_prefix.clone(),
+            scope_unit: self.scope_unit.clone(),
+        }
+    }
+
+    pub fn launch_environment(&self) -> &LaunchEnvironment {
+        &self.launch_environment
+    }
+
+    /// The latest sample of the game's scope, empty if it does not run in one
+    pub fn resource_usage(&self) -> ScopeUsage {
+        *self.resource_usage.lock().unwrap()
+    }
+
     #[allow(dead_code)]
     #[cfg(debug_assertions)]
     pub fn null() -> Self {
@@ -40,6 +78,12 @@ impl ProcessHandle {
             pid: 0,
             process_token: Uuid::new_v4(),
             wait_handle: None,
+            exit_rx: watch::channel(None).1,
+            tracked: TrackedProcesses::new(0),
+            wine_prefix: None,
+            scope_unit: None,
+            resource_usage: Arc::new(Mutex::new(ScopeUsage::default())),
+            launch_environment: LaunchEnvironment::default(),
         }
     }
 }
@@ -54,14 +98,16 @@ impl CompatLauncher {
         *app_state_lock = Some(app_state);
     }
 
-    pub fn launch_app(&self, app: &SteamApp, compat_version: Option<ProtonLaunchInfo>, modifiers: Vec<Box<dyn LaunchModifier>>, sock_tx: Sender<String>, logger: Arc<Mutex<LogChannel>>) -> anyhow::Result<ProcessHandle> {
+    /// `launch_options` are applied after the modifiers, a wrapper in them also wraps Proton.
+    /// `use_systemd_scope` runs the game in its own transient systemd user scope, see [`systemd_scope`]
+    pub fn launch_app(&self, app: &SteamApp, compat_version: Option<ProtonLaunchInfo>, modifiers: Vec<Box<dyn LaunchModifier>>, launch_options: &LaunchOptions, use_systemd_scope: bool, logger: Arc<Mutex<LogChannel>>) -> anyhow::Result<ProcessHandle> {
         match compat_version {
-            Some(compat_version) => self.launch_app_compat(app, compat_version, modifiers, sock_tx, logger),
-            None => self.launch_app_native(app, modifiers, sock_tx, logger),
+            Some(compat_version) => self.launch_app_compat(app, compat_version, modifiers, launch_options, use_systemd_scope, logger),
+            None => self.launch_app_native(app, modifiers, launch_options, use_systemd_scope, logger),
         }
     }
 
-    fn launch_app_native(&self, app: &SteamApp, modifiers: Vec<Box<dyn LaunchModifier>>, sock_tx: Sender<String>, logger: Arc<Mutex<LogChannel>>) -> anyhow::Result<ProcessHandle> {
+    fn launch_app_native(&self, app: &SteamApp, modifiers: Vec<Box<dyn LaunchModifier>>, launch_options: &LaunchOptions, use_systemd_scope: bool, logger: Arc<Mutex<LogChannel>>) -> anyhow::Result<ProcessHandle> {
         if !app.working_directory.exists() {
             bail!("The specified working directory does not exist.");
         }
@@ -89,30 +135,12 @@ impl CompatLauncher {
         for modifier in modifiers {
             modifier.apply(&mut process, app, None)?;
         }
+        let process = launch_options.apply(process);
 
-        let mut child = process.spawn()?;
-        let pid = child.id().unwrap();
-
-        LogChannel::connect_tokio(logger, &mut child);
-
-        let app_state_clone = self.app_state.clone();
-        Ok(ProcessHandle {
-            pid,
-            process_token,
-            wait_handle: Some(tokio::task::spawn(async move {
-                println!("Waiting for child process to exit (id={})", pid);
-                let status = child.wait().await;
-                println!("The child process has exited with status {:?}", status);
-                _ = sock_tx.send("inactive".to_owned());
-                let app_state = app_state_clone.write().await;
-                let app_state = app_state.as_ref().unwrap();
-                let mut app_state = app_state.lock().await;
-                _ = app_state.game_process_died();
-            })),
-        })
+        self.spawn(process, process_token, None, use_systemd_scope, logger)
     }
 
-    fn launch_app_compat(&self, app: &SteamApp, compat_version: ProtonLaunchInfo, modifiers: Vec<Box<dyn LaunchModifier>>, sock_tx: Sender<String>, logger: Arc<Mutex<LogChannel>>) -> anyhow::Result<ProcessHandle> {
+    fn launch_app_compat(&self, app: &SteamApp, compat_version: ProtonLaunchInfo, modifiers: Vec<Box<dyn LaunchModifier>>, launch_options: &LaunchOptions, use_systemd_scope: bool, logger: Arc<Mutex<LogChannel>>) -> anyhow::Result<ProcessHandle> {
         if !app.working_directory.exists() {
             bail!("The specified working directory does not exist.");
         }
@@ -168,26 +196,111 @@ impl CompatLauncher {
         for modifier in modifiers {
             modifier.apply(&mut process, app, Some(&compat_version.version))?;
         }
+        let process = launch_options.apply(process);
+
+        // Proton keeps the wine prefix in the "pfx" folder of the compat data path
+        let compat_data_path = process.as_std().get_envs()
+            .find(|(key, _)| *key == "STEAM_COMPAT_DATA_PATH")
+            .and_then(|(_, value)| value.map(PathBuf::from));
+        let wine_prefix = compat_data_path
+            .zip(compat_version.version.find_wine_binary("wineserver"))
+            .map(|(compat_data_path, wineserver)| (compat_data_path.join("pfx"), wineserver));
+
+        self.spawn(process, process_token, wine_prefix, use_systemd_scope, logger)
+    }
+
+    fn spawn(&self, process: process::Command, process_token: Uuid, wine_prefix: Option<(PathBuf, PathBuf)>, use_systemd_scope: bool, logger: Arc<Mutex<LogChannel>>) -> anyhow::Result<ProcessHandle> {
+        let launch_environment = LaunchEnvironment::capture(&process);
+        let (mut process, scope_unit) = match use_systemd_scope {
+            true if systemd_scope::is_available() => {
+                let unit = systemd_scope::unit_name(&process_token);
+                (systemd_scope::wrap_command(&process, &unit), Some(unit))
+            }
+            true => {
+                println!("systemd is not available, the game is started without a scope");
+                (process, None)
+            }
+            false => (process, None),
+        };
+
+        // The game leads its own process group, so it can be signalled as a whole
+        process.process_group(0);
+        process_tree::become_subreaper();
 
         let mut child = process.spawn()?;
         let pid = child.id().unwrap();
 
         LogChannel::connect_tokio(logger, &mut child);
 
+        let mut process_handle = self.wait_for_exit(child, pid, process_token, wine_prefix, scope_unit);
+        process_handle.launch_environment = launch_environment;
+
+        Ok(process_handle)
+    }
+
+    fn wait_for_exit(&self, mut child: process::Child, pid: u32, process_token: Uuid, wine_prefix: Option<(PathBuf, PathBuf)>, scope_unit: Option<String>) -> ProcessHandle {
+        let (exit_tx, exit_rx) = watch::channel(None);
+        let tracked = TrackedProcesses::new(pid);
+        let resource_usage = Arc::new(Mutex::new(ScopeUsage::default()));
         let app_state_clone = self.app_state.clone();
-        Ok(ProcessHandle {
+        let tracked_clone = tracked.clone();
+        let scope_clone = scope_unit.clone().map(|unit| (unit, resource_usage.clone()));
+
+        ProcessHandle {
             pid,
             process_token,
             wait_handle: Some(tokio::task::spawn(async move {
                 println!("Waiting for game process to exit (id={})", pid);
-                let status = child.wait().await;
+                let status = watch_until_exit(&mut child, &tracked_clone, scope_clone.as_ref()).await;
                 println!("The child process has exited with status {:?}", status);
-                _ = sock_tx.send("inactive".to_owned());
-                let app_state = app_state_clone.write().await;
-                let app_state = app_state.as_ref().unwrap();
-                let mut app_state = app_state.lock().await;
-                _ = app_state.game_process_died();
+                _ = exit_tx.send(status.as_ref().ok().copied());
+
+                {
+                    let app_state = app_state_clone.write().await;
+                    let app_state = app_state.as_ref().unwrap();
+                    let mut app_state = app_state.lock().await;
+                    _ = app_state.game_process_died(&process_token, status.ok());
+                }
+
+                // Processes that outlive the game (e.g. the wineserver) are reaped once they exit
+                while !tracked_clone.refresh().is_empty() {
+                    tokio::time::sleep(USAGE_SAMPLE_INTERVAL).await;
+                }
             })),
-        })
+            exit_rx,
+            tracked,
+            wine_prefix,
+            scope_unit,
+            resource_usage,
+            launch_environment: LaunchEnvironment::default(),
+        }
+    }
+}
+
+/// Walks the game's process tree until the main process exits. The scope, if any, is collected
+/// as soon as it is empty, so its usage is sampled while the game runs.
+async fn watch_until_exit(child: &mut process::Child, tracked: &TrackedProcesses, scope: Option<&(String, Arc<Mutex<ScopeUsage>>)>) -> std::io::Result<ExitStatus> {
+    let mut last_sample: Option<Instant> = None;
+    loop {
+        tracked.refresh();
+        if let Some((unit, resource_usage)) = scope
+            && last_sample.is_none_or(|sampled_at| sampled_at.elapsed() >= USAGE_SAMPLE_INTERVAL) {
+            if let Ok(sample) = systemd_scope::query_usage(unit).await {
+                resource_usage.lock().unwrap().merge(sample);
+            }
+            last_sample = Some(Instant::now());
+        }
+
+        tokio::select! {
+            status = child.wait() => {
+                // Processes that outlive the main one (e.g. the wineserver) may still keep the scope around
+                if let Some((unit, resource_usage)) = scope
+                    && let Ok(sample) = systemd_scope::query_usage(unit).await {
+                    resource_usage.lock().unwrap().merge(sample);
+                }
+                return status;
+            }
+            _ = tokio::time::sleep(TRACKING_INTERVAL) => {}
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const LIBRARY_CACHE: &str = "appcache/librarycache";
+
+/// Cover candidates in order of preference: portrait library art first, then the wide banners
+const COVER_FILE_NAMES: &[&str] = &[
+    "library_600x900_2x.jpg",
+    "library_600x900.jpg",
+    "library_capsule.jpg",
+    "header.jpg",
+    "library_header.jpg",
+    "library_hero.jpg",
+];
+
+/// Read-only access to the artwork Steam caches locally for every app in the library
+pub struct LibraryCache {
+    root: PathBuf,
+}
+
+impl LibraryCache {
+    pub fn locate() -> anyhow::Result<Self> {
+        let steam_dir = steamlocate::SteamDir::locate()?;
+
+        Ok(Self::new(steam_dir.path().join(LIBRARY_CACHE)))
+    }
+
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn find_cover(&self, app_id: u32) -> Option<PathBuf> {
+        COVER_FILE_NAMES.iter()
+            .find_map(|file_name| self.find_image(app_id, file_name))
+    }
+
+    pub fn load_cover(&self, app_id: u32) -> Option<Vec<u8>> {
+        self.find_cover(app_id)
+            .and_then(|path| fs::read(path).ok())
+    }
+
+    fn find_image(&self, app_id: u32, file_name: &str) -> Option<PathBuf> {
+        // Newer clients store the images in a folder per app, sometimes inside a hashed subfolder
+        let app_dir = self.root.join(app_id.to_string());
+        if let Some(path) = Self::find_file_recursive(&app_dir, file_name, 2) {
+            return Some(path);
+        }
+
+        // Older clients keep everything in a single flat folder
+        let legacy_path = self.root.join(format!("{}_{}", app_id, file_name));
+        legacy_path.is_file().then_some(legacy_path)
+    }
+
+    fn find_file_recursive(dir: &Path, file_name: &str, max_depth: usize) -> Option<PathBuf> {
+        let direct_path = dir.join(file_name);
+        if direct_path.is_file() {
+            return Some(direct_path);
+        }
+
+        if max_depth == 0 {
+            return None;
+        }
+
+        fs::read_dir(dir).ok()?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .find_map(|path| Self::find_file_recursive(&path, file_name, max_depth - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("svrl_library_cache_{}_{}", name, std::process::id()));
+        _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_prefers_portrait_art() {
+        let root = temp_cache("portrait");
+        fs::create_dir_all(root.join("620980/4f1e")).unwrap();
+        fs::write(root.join("620980/header.jpg"), b"header").unwrap();
+        fs::write(root.join("620980/4f1e/library_600x900.jpg"), b"portrait").unwrap();
+
+        let cache = LibraryCache::new(root.clone());
+        assert_eq!(cache.load_cover(620980), Some(b"portrait".to_vec()));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_legacy_flat_layout() {
+        let root = temp_cache("legacy");
+        fs::write(root.join("438100_header.jpg"), b"header").unwrap();
+
+        let cache = LibraryCache::new(root.clone());
+        assert_eq!(cache.load_cover(438100), Some(b"header".to_vec()));
+        assert_eq!(cache.load_cover(1), None);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
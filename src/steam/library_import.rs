@@ -0,0 +1,207 @@
+use crate::database::DbPool;
+use crate::events::ServerEvent;
+use crate::models::{GameFields, NewGame};
+use crate::schema::games::dsl::{games, steam_app_id};
+use crate::steam::steam_interface::{SteamApp, SteamAppPlatform, SteamInterface};
+use crate::StdMutex;
+use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl, SqliteConnection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::broadcast::Sender;
+use ts_rs::TS;
+use uuid::Uuid;
+
+const DEFAULT_IMPORT_BACKEND: &str = "wivrn";
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCandidate {
+    pub steam_app_id: u32,
+    pub title: String,
+    pub needs_proton: bool,
+    pub suggested_proton_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub imported: Vec<ImportCandidate>,
+    pub skipped_app_ids: Vec<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRequest {
+    /// The Steam apps to import; every importable VR app is imported when omitted
+    pub app_ids: Option<Vec<u32>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum ImportJobStatus {
+    #[default]
+    Idle,
+    Running,
+    Finished { report: ImportReport },
+    Failed { message: String },
+}
+
+/// Runs Steam library imports in the background, since scanning `appinfo.vdf` takes a while
+#[derive(Default)]
+pub struct SteamImportJob {
+    status: Arc<StdMutex<ImportJobStatus>>,
+}
+
+impl SteamImportJob {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_status(&self) -> ImportJobStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Starts a new import unless one is already running. Returns false if it was rejected.
+    pub fn start(&self, request: ImportRequest, pool: DbPool, sock_tx: Sender<ServerEvent>) -> bool {
+        {
+            let mut status = self.status.lock().unwrap();
+            if let ImportJobStatus::Running = *status {
+                return false;
+            }
+            *status = ImportJobStatus::Running;
+        }
+
+        let status = self.status.clone();
+        tokio::task::spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                let steam_api = SteamInterface::new();
+                let connection = &mut pool.get()?;
+                import_apps(&steam_api, connection, request.app_ids.as_deref())
+            }).await;
+
+            let new_status = match result {
+                Ok(Ok(report)) => {
+                    println!("Imported {} game(s) from the Steam library", report.imported.len());
+                    if !report.imported.is_empty() {
+                        _ = sock_tx.send(ServerEvent::LibraryChanged);
+                    }
+                    ImportJobStatus::Finished { report }
+                }
+                Ok(Err(error)) => ImportJobStatus::Failed { message: error.to_string() },
+                Err(error) => ImportJobStatus::Failed { message: format!("The import task has crashed: {}", error) },
+            };
+
+            *status.lock().unwrap() = new_status;
+        });
+
+        true
+    }
+}
+
+/// Lists the installed Steam VR apps that are not in the games table yet
+pub fn find_import_candidates(steam_api: &SteamInterface, connection: &mut SqliteConnection) -> anyhow::Result<Vec<ImportCandidate>> {
+    let existing_ids = load_existing_app_ids(connection)?;
+    let proton_versions = steam_api.get_proton_versions().unwrap_or_default();
+    let suggested_proton = SteamInterface::guess_proton_version(&proton_versions)
+        .map(|v| v.name.clone());
+
+    let mut candidates = steam_api.get_installed_apps(None)?
+        .into_iter()
+        .filter(|app| app.is_vr_app && !existing_ids.contains(&app.steam_id))
+        .map(|app| to_candidate(app, suggested_proton.clone()))
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| a.title.cmp(&b.title));
+
+    Ok(candidates)
+}
+
+/// Inserts the selected apps into the games table. Apps that are already present are skipped,
+/// so running the same import twice never creates duplicate entries.
+pub fn import_apps(steam_api: &SteamInterface, connection: &mut SqliteConnection, app_ids: Option<&[u32]>) -> anyhow::Result<ImportReport> {
+    let candidates = find_import_candidates(steam_api, connection)?;
+
+    connection.transaction(|connection| {
+        let existing_ids = load_existing_app_ids(connection)?;
+        let mut report = ImportReport::default();
+        let mut seen_ids = HashSet::new();
+
+        let selected = match app_ids {
+            Some(app_ids) => {
+                for app_id in app_ids {
+                    if !candidates.iter().any(|c| c.steam_app_id == *app_id) {
+                        report.skipped_app_ids.push(*app_id);
+                    }
+                }
+                candidates.into_iter()
+                    .filter(|c| app_ids.contains(&c.steam_app_id))
+                    .collect::<Vec<_>>()
+            }
+            None => candidates,
+        };
+
+        for candidate in selected {
+            if existing_ids.contains(&candidate.steam_app_id) || !seen_ids.insert(candidate.steam_app_id) {
+                report.skipped_app_ids.push(candidate.steam_app_id);
+                continue;
+            }
+
+            let new_game = NewGame {
+                id: Uuid::new_v4().to_string(),
+                fields: GameFields {
+                    title: candidate.title.clone(),
+                    vr_backend: DEFAULT_IMPORT_BACKEND.into(),
+                    vr_backend_args: String::new(),
+                    pressure_vessel: false,
+                    steam_app_id: Some(candidate.steam_app_id as i64),
+                    proton_version: match candidate.needs_proton {
+                        true => candidate.suggested_proton_version.clone(),
+                        false => None,
+                    },
+                    command_line: None,
+                    use_overlay: false,
+                    use_systemd_scope: false,
+                    winetricks_verbs: String::new(),
+                    launch_options: String::new(),
+                    steam_launch_config: None,
+                },
+            };
+
+            diesel::insert_into(games)
+                .values(&new_game)
+                .execute(connection)?;
+            report.imported.push(candidate);
+        }
+
+        Ok(report)
+    })
+}
+
+fn load_existing_app_ids(connection: &mut SqliteConnection) -> anyhow::Result<HashSet<u32>> {
+    let existing_ids = games
+        .select(steam_app_id)
+        .filter(steam_app_id.is_not_null())
+        .load::<Option<i64>>(connection)?
+        .into_iter()
+        .flatten()
+        .map(|id| id as u32)
+        .collect();
+
+    Ok(existing_ids)
+}
+
+fn to_candidate(app: SteamApp, suggested_proton: Option<String>) -> ImportCandidate {
+    let needs_proton = app.platform == SteamAppPlatform::Windows;
+    ImportCandidate {
+        steam_app_id: app.steam_id,
+        title: app.title,
+        needs_proton,
+        suggested_proton_version: match needs_proton {
+            true => suggested_proton,
+            false => None,
+        },
+    }
+}
@@ -1,4 +1,11 @@
 pub mod steam_interface;
 mod vfd_format;
 pub mod launcher;
-pub mod launch_modifiers;
\ No newline at end of file
+pub mod prefix;
+pub mod process_tree;
+pub mod systemd_scope;
+pub mod winetricks;
+pub mod launch_modifiers;
+pub mod launch_options;
+pub mod library_import;
+pub mod library_cache;
@@ -0,0 +1,265 @@
+use crate::command_parser::parse_linux_command;
+use crate::models::Game;
+use crate::schema::games;
+use chrono::{DateTime, Utc};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SqliteConnection};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::env;
+use std::fs::{self, File};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tar::{Archive, Builder};
+use ts_rs::TS;
+use uuid::Uuid;
+
+const COMPATDATA: &str = "steamapps/compatdata";
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct PrefixInfo {
+    pub prefix_id: String,
+    pub path: String,
+    /// Proton creates the prefix on the first launch
+    pub exists: bool,
+    pub size_bytes: u64,
+    /// The prefix version Proton has written to the `version` file, e.g. "9.0-201"
+    pub proton_version: Option<String>,
+    pub created_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct PrefixBackup {
+    pub file_name: String,
+    pub size_bytes: u64,
+    pub created_at: i64,
+}
+
+/// Returns the stored prefix id of `game`, assigning one on first use. Steam games use their
+/// app id like Steam does. Other games adopt the id older versions derived from the executable
+/// path, as long as that prefix exists, and get a new random id otherwise.
+pub fn ensure_prefix_id(connection: &mut SqliteConnection, game: &Game) -> anyhow::Result<String> {
+    if let Some(prefix_id) = &game.prefix_id {
+        return Ok(prefix_id.clone());
+    }
+
+    let prefix_id = match (game.steam_app_id, &game.command_line) {
+        (Some(steam_app_id), _) => steam_app_id.to_string(),
+        (None, Some(command_line)) => {
+            let command = parse_linux_command(command_line)
+                .map_err(|err| anyhow::anyhow!("Could not parse launch command: {:?}", err))?;
+            let legacy_id = legacy_prefix_id(&command.executable);
+            match compat_data_path(&legacy_id)?.exists() {
+                true => legacy_id,
+                false => new_prefix_id(),
+            }
+        }
+        (None, None) => new_prefix_id(),
+    };
+
+    println!("Assigned the prefix id {} to game {}", prefix_id, game.id);
+    diesel::update(games::table.find(&game.id))
+        .set(games::prefix_id.eq(&prefix_id))
+        .execute(connection)?;
+
+    Ok(prefix_id)
+}
+
+/// The folder Proton keeps the prefix of `prefix_id` in, passed as `STEAM_COMPAT_DATA_PATH`
+pub fn compat_data_path(prefix_id: &str) -> anyhow::Result<PathBuf> {
+    let steam_home = steamlocate::SteamDir::locate()?;
+
+    Ok(steam_home.path().join(COMPATDATA).join(prefix_id))
+}
+
+pub fn inspect(prefix_id: &str) -> anyhow::Result<PrefixInfo> {
+    let path = compat_data_path(prefix_id)?;
+    let proton_version = fs::read_to_string(path.join("version")).ok()
+        .map(|version| version.trim().to_string());
+    let created_at = fs::metadata(&path).and_then(|m| m.created()).ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|time| time.as_secs() as i64);
+
+    Ok(PrefixInfo {
+        prefix_id: prefix_id.to_string(),
+        path: path.to_string_lossy().into_owned(),
+        exists: path.exists(),
+        size_bytes: directory_size(&path),
+        proton_version,
+        created_at,
+    })
+}
+
+/// Deletes the prefix, Proton creates a fresh one on the next launch
+pub fn reset(prefix_id: &str) -> anyhow::Result<()> {
+    let path = compat_data_path(prefix_id)?;
+    if path.exists() {
+        fs::remove_dir_all(&path)?;
+    }
+
+    Ok(())
+}
+
+/// Where prefix backups are kept, next to the `logs` folder
+pub fn backups_dir() -> anyhow::Result<PathBuf> {
+    Ok(env::current_exe()?
+        .parent().unwrap()
+        .join("prefix_backups"))
+}
+
+pub fn backup(prefix_id: &str) -> anyhow::Result<PrefixBackup> {
+    create_backup(&compat_data_path(prefix_id)?, &backups_dir()?, prefix_id)
+}
+
+/// Replaces the prefix with the contents of a backup that was made for the same prefix id
+pub fn restore(prefix_id: &str, file_name: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(is_backup_of(file_name, prefix_id), "'{}' is not a backup of prefix {}", file_name, prefix_id);
+    let archive_path = backups_dir()?.join(file_name);
+    anyhow::ensure!(archive_path.exists(), "The backup '{}' does not exist", file_name);
+
+    restore_backup(&archive_path, &compat_data_path(prefix_id)?)
+}
+
+/// The backups of `prefix_id`, newest first
+pub fn list_backups(prefix_id: &str) -> anyhow::Result<Vec<PrefixBackup>> {
+    let backups_dir = backups_dir()?;
+    if !backups_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = fs::read_dir(backups_dir)?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            let metadata = entry.metadata().ok()?;
+            is_backup_of(&file_name, prefix_id).then(|| PrefixBackup {
+                file_name,
+                size_bytes: metadata.len(),
+                created_at: metadata.modified().ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|time| time.as_secs() as i64)
+                    .unwrap_or_default(),
+            })
+        })
+        .collect::<Vec<_>>();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+
+    Ok(backups)
+}
+
+fn create_backup(source_dir: &Path, output_dir: &Path, prefix_id: &str) -> anyhow::Result<PrefixBackup> {
+    anyhow::ensure!(source_dir.exists(), "The prefix has not been created yet");
+    fs::create_dir_all(output_dir)?;
+
+    let now = SystemTime::now();
+    let datetime: DateTime<Utc> = now.into();
+    let file_name = format!("{}_{}.tar.gz", prefix_id, datetime.format("%Y-%m-%d_%H-%M-%S"));
+    let archive_path = output_dir.join(&file_name);
+
+    let encoder = GzEncoder::new(File::create(&archive_path)?, Compression::default());
+    let mut tar = Builder::new(encoder);
+    // Wine prefixes link into the system and the Proton install, those links must stay links
+    tar.follow_symlinks(false);
+    tar.append_dir_all(".", source_dir)?;
+    tar.into_inner()?.finish()?;
+
+    Ok(PrefixBackup {
+        file_name,
+        size_bytes: fs::metadata(&archive_path)?.len(),
+        created_at: now.duration_since(UNIX_EPOCH)?.as_secs() as i64,
+    })
+}
+
+fn restore_backup(archive_path: &Path, target_dir: &Path) -> anyhow::Result<()> {
+    // Unpacked next to the prefix first, so a broken archive leaves the current prefix intact
+    let staging_dir = target_dir.with_extension("restoring");
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir)?;
+    }
+
+    let mut archive = Archive::new(GzDecoder::new(File::open(archive_path)?));
+    archive.set_preserve_permissions(true);
+    if let Err(error) = archive.unpack(&staging_dir) {
+        _ = fs::remove_dir_all(&staging_dir);
+        return Err(error.into());
+    }
+
+    if target_dir.exists() {
+        fs::remove_dir_all(target_dir)?;
+    }
+    fs::rename(&staging_dir, target_dir)?;
+
+    Ok(())
+}
+
+/// Backups are only accessed by file name, never by path
+fn is_backup_of(file_name: &str, prefix_id: &str) -> bool {
+    file_name.strip_prefix(prefix_id).is_some_and(|rest| rest.starts_with('_'))
+        && file_name.ends_with(".tar.gz")
+        && !file_name.contains(['/', '\\'])
+}
+
+/// The total size of the files in `path`, without following symbolic links
+fn directory_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries.filter_map(Result::ok)
+        .filter_map(|entry| Some((entry.path(), entry.file_type().ok()?)))
+        .map(|(path, file_type)| match file_type {
+            t if t.is_dir() => directory_size(&path),
+            t if t.is_file() => fs::symlink_metadata(&path).map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// The id older versions derived from the executable path. `DefaultHasher` is not stable
+/// across Rust versions, so this is only used to find existing prefixes.
+fn legacy_prefix_id(executable: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    executable.hash(&mut hasher);
+    let hash = hasher.finish();
+    let hash_str = format!("{:020}", hash);
+    hash_str.chars().take(20).collect()
+}
+
+/// Numeric like Steam's own ids, so Proton and other tools treat it like any other prefix
+fn new_prefix_id() -> String {
+    let random = u64::from_le_bytes(Uuid::new_v4().as_bytes()[..8].try_into().unwrap());
+    format!("{:020}", random)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_and_restore() {
+        let root = env::temp_dir().join(format!("svrl-prefix-{}", Uuid::new_v4()));
+        let prefix_dir = root.join("compatdata/123");
+        fs::create_dir_all(prefix_dir.join("pfx/drive_c")).unwrap();
+        fs::write(prefix_dir.join("version"), "9.0-201\n").unwrap();
+        fs::write(prefix_dir.join("pfx/drive_c/save.dat"), "before").unwrap();
+        std::os::unix::fs::symlink("/nonexistent", prefix_dir.join("pfx/dosdevices")).unwrap();
+        assert_eq!(directory_size(&prefix_dir), 14);
+
+        let backup = create_backup(&prefix_dir, &root.join("backups"), "123").unwrap();
+        assert!(is_backup_of(&backup.file_name, "123"));
+        assert!(!is_backup_of(&backup.file_name, "12"));
+
+        fs::write(prefix_dir.join("pfx/drive_c/save.dat"), "after").unwrap();
+        restore_backup(&root.join("backups").join(&backup.file_name), &prefix_dir).unwrap();
+        assert_eq!(fs::read_to_string(prefix_dir.join("pfx/drive_c/save.dat")).unwrap(), "before");
+        assert!(fs::symlink_metadata(prefix_dir.join("pfx/dosdevices")).unwrap().file_type().is_symlink());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
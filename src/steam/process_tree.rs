@@ -0,0 +1,248 @@
+use crate::steam::systemd_scope;
+use nix::errno::Errno;
+use nix::libc::pid_t;
+use nix::sys::prctl;
+use nix::sys::signal::{kill, killpg, Signal};
+use nix::sys::wait::{waitpid, WaitPidFlag};
+use nix::unistd::Pid;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+use sysinfo::{ProcessRefreshKind, ProcessStatus, ProcessesToUpdate, System, UpdateKind};
+use tokio::process;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// How long a game gets to exit on its own after SIGTERM, unless the caller asks otherwise
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(10);
+const KILL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Every process started for a game. The main process leads its own process group, and its
+/// descendants are tracked while it runs, see [`TrackedProcesses`]. Every descendant also
+/// inherits the `SVRL_TOKEN` environment variable, which finds whatever escaped the tracking.
+#[derive(Debug, Clone)]
+pub struct GameProcessTree {
+    pub pgid: u32,
+    pub process_token: Uuid,
+    pub tracked: TrackedProcesses,
+    /// Set by the launcher's wait task once the main process has exited
+    pub exit_rx: watch::Receiver<Option<ExitStatus>>,
+    /// The Proton prefix and the `wineserver` that manages it, only for compat launches
+    pub wine_prefix: Option<(PathBuf, PathBuf)>,
+    /// The transient systemd scope the game runs in, if any
+    pub scope_unit: Option<String>,
+}
+
+impl GameProcessTree {
+    /// Stops the game in stages: SIGTERM, SIGKILL once `grace_period` has passed, and finally
+    /// `wineserver -k` for anything left in the prefix. The systemd scope of the game, if any,
+    /// is stopped last. Returns the main process' exit status.
+    pub async fn terminate(&self, grace_period: Duration) -> Option<ExitStatus> {
+        println!("Sending SIGTERM to the game (pgid={})", self.pgid);
+        self.signal(Signal::SIGTERM).await;
+
+        if !self.wait_for_exit(grace_period).await {
+            println!("The game did not exit within {:?}, sending SIGKILL", grace_period);
+            self.signal(Signal::SIGKILL).await;
+            if !self.wait_for_exit(KILL_TIMEOUT).await {
+                eprintln!("The game process has not exited after SIGKILL (pid={})", self.pgid);
+            }
+        }
+
+        // The main process may exit before its children do. Processes that were orphaned between
+        // two refreshes of the tree are only found by their environment.
+        let mut leftovers = self.tracked.refresh();
+        let escaped = find_token_processes(&self.process_token).into_iter()
+            .filter(|pid| !leftovers.contains(pid))
+            .collect::<Vec<_>>();
+        self.tracked.adopt(&escaped);
+        leftovers.extend(escaped);
+        if !leftovers.is_empty() {
+            println!("Killing {} leftover game process(es)", leftovers.len());
+            self.signal(Signal::SIGKILL).await;
+        }
+
+        if let Some((wine_prefix, wineserver)) = &self.wine_prefix
+            && let Err(error) = kill_wineserver(wineserver, wine_prefix).await {
+            eprintln!("Failed to stop the wineserver of {:?}: {:?}", wine_prefix, error);
+        }
+
+        if let Some(unit) = &self.scope_unit
+            && let Err(error) = systemd_scope::stop(unit).await {
+            eprintln!("Failed to stop {}: {:?}", unit, error);
+        }
+
+        *self.exit_rx.borrow()
+    }
+
+    async fn signal(&self, signal: Signal) {
+        // The unit also contains processes that have left both the group and the environment
+        if let Some(unit) = &self.scope_unit
+            && let Err(error) = systemd_scope::kill(unit, signal).await {
+            eprintln!("Failed to send {} to {}: {:?}", signal, unit, error);
+        }
+
+        match killpg(Pid::from_raw(self.pgid as pid_t), signal) {
+            Ok(_) | Err(Errno::ESRCH) => {}
+            Err(error) => eprintln!("Failed to send {} to process group {}: {}", signal, self.pgid, error),
+        }
+
+        for pid in self.tracked.refresh() {
+            _ = kill(Pid::from_raw(pid as pid_t), signal);
+        }
+    }
+
+    /// Returns false if the main process is still running after `timeout`
+    async fn wait_for_exit(&self, timeout: Duration) -> bool {
+        let mut exit_rx = self.exit_rx.clone();
+        // A closed channel means the wait task is gone, which only happens once the process has exited
+        tokio::time::timeout(timeout, exit_rx.wait_for(|status| status.is_some())).await.is_ok()
+    }
+}
+
+/// Makes the launcher the new parent of processes that are orphaned below it, instead of init.
+/// A game process whose parent has exited (e.g. a daemonized wine service) then stays a
+/// descendant of the launcher, together with everything it starts later on.
+pub fn become_subreaper() {
+    static SUBREAPER: Once = Once::new();
+    SUBREAPER.call_once(|| {
+        if let Err(error) = prctl::set_child_subreaper(true) {
+            eprintln!("Failed to become a child subreaper, orphaned game processes may be missed: {}", error);
+        }
+    });
+}
+
+/// The processes known to belong to a game, by PID and start time, so a reused PID is not
+/// mistaken for one of them. The launcher's wait task refreshes them while the game runs.
+#[derive(Debug, Clone)]
+pub struct TrackedProcesses {
+    main_pid: u32,
+    processes: Arc<Mutex<HashMap<u32, u64>>>,
+}
+
+impl TrackedProcesses {
+    pub fn new(main_pid: u32) -> Self {
+        let tracked = Self {
+            main_pid,
+            processes: Arc::new(Mutex::new(HashMap::new())),
+        };
+        tracked.adopt(&[main_pid]);
+
+        tracked
+    }
+
+    /// Adds the descendants of every tracked process to them, and reaps the tracked processes that
+    /// have exited after being orphaned. Returns the tracked processes that are still alive.
+    pub fn refresh(&self) -> Vec<u32> {
+        let mut sys = System::new();
+        sys.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::nothing());
+
+        let mut children = HashMap::<_, Vec<_>>::new();
+        for (pid, process) in sys.processes() {
+            if let Some(parent) = process.parent() {
+                children.entry(parent).or_default().push(*pid);
+            }
+        }
+
+        let mut processes = self.processes.lock().unwrap();
+        let mut queue = processes.iter()
+            .map(|(pid, start_time)| (sysinfo::Pid::from_u32(*pid), *start_time))
+            .filter(|(pid, start_time)| sys.process(*pid).is_some_and(|process| process.start_time() == *start_time))
+            .map(|(pid, _)| pid)
+            .collect::<Vec<_>>();
+        let mut found = HashMap::new();
+        while let Some(pid) = queue.pop() {
+            if let Some(process) = sys.process(pid)
+                && found.insert(pid.as_u32(), process.start_time()).is_none() {
+                queue.extend(children.get(&pid).into_iter().flatten().copied());
+            }
+        }
+        *processes = found;
+
+        let launcher_pid = sysinfo::Pid::from_u32(std::process::id());
+        let mut alive = Vec::new();
+        for pid in processes.keys() {
+            let process = &sys.processes()[&sysinfo::Pid::from_u32(*pid)];
+            if process.status() != ProcessStatus::Zombie {
+                alive.push(*pid);
+            } else if *pid != self.main_pid && process.parent() == Some(launcher_pid) {
+                // The main process is waited for by the launcher, everything else would stay a zombie
+                _ = waitpid(Pid::from_raw(*pid as pid_t), Some(WaitPidFlag::WNOHANG));
+            }
+        }
+
+        alive
+    }
+
+    /// Tracks processes that were found some other way
+    pub fn adopt(&self, pids: &[u32]) {
+        let pids = pids.iter().map(|pid| sysinfo::Pid::from_u32(*pid)).collect::<Vec<_>>();
+        let mut sys = System::new();
+        sys.refresh_processes_specifics(ProcessesToUpdate::Some(&pids), true, ProcessRefreshKind::nothing());
+
+        let mut processes = self.processes.lock().unwrap();
+        for (pid, process) in sys.processes() {
+            processes.insert(pid.as_u32(), process.start_time());
+        }
+    }
+}
+
+/// Returns the PIDs of every process that carries the given `SVRL_TOKEN`
+pub fn find_token_processes(process_token: &Uuid) -> Vec<u32> {
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_environ(UpdateKind::Always),
+    );
+
+    let expected = format!("SVRL_TOKEN={}", process_token);
+    sys.processes().iter()
+        .filter(|(_, process)| process.environ().iter().any(|var| var.to_str() == Some(expected.as_str())))
+        .map(|(pid, _)| pid.as_u32())
+        .collect()
+}
+
+async fn kill_wineserver(wineserver: &PathBuf, wine_prefix: &PathBuf) -> anyhow::Result<()> {
+    if !wine_prefix.exists() {
+        return Ok(());
+    }
+
+    let status = process::Command::new(wineserver)
+        .arg("-k")
+        .env("WINEPREFIX", wine_prefix)
+        .status()
+        .await?;
+    println!("wineserver -k exited with {}", status);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orphans_stay_tracked() {
+        become_subreaper();
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "sleep 30 & sleep 0.5"])
+            .spawn()
+            .unwrap();
+        let tracked = TrackedProcesses::new(child.id());
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(tracked.refresh().len(), 3);
+
+        // The background sleep is orphaned once the shell exits, and adopted by the test process
+        child.wait().unwrap();
+        let orphans = tracked.refresh();
+        assert_eq!(orphans.len(), 1);
+
+        kill(Pid::from_raw(orphans[0] as pid_t), Signal::SIGKILL).unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(tracked.refresh().is_empty());
+        assert_eq!(waitpid(Pid::from_raw(orphans[0] as pid_t), Some(WaitPidFlag::WNOHANG)), Err(Errno::ECHILD));
+    }
+}
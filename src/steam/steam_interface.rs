@@ -1,6 +1,9 @@
-use crate::steam::vfd_format::AppInfoDatabase;
+use crate::command_parser::tokenize_steam_arguments;
+use crate::steam::vfd_format::{AppInfoDatabase, KVValue};
+use serde::Serialize;
 use std::collections::HashSet;
 use std::path::PathBuf;
+use ts_rs::TS;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum SteamAppPlatform {
@@ -29,12 +32,62 @@ pub struct ProtonVersion {
     pub executable_path: PathBuf,
 }
 
+impl ProtonVersion {
+    /// Finds one of the wine binaries (e.g. `wine` or `wineserver`) that ship with this Proton build
+    pub fn find_wine_binary(&self, name: &str) -> Option<PathBuf> {
+        let proton_dir = self.executable_path.parent()?;
+        ["files/bin", "dist/bin"].into_iter()
+            .map(|bin_dir| proton_dir.join(bin_dir).join(name))
+            .find(|path| path.exists())
+    }
+}
+
 #[derive(Debug)]
 pub struct ProtonLaunchInfo {
     pub version: ProtonVersion,
     pub use_pressure_vessel: bool,
 }
 
+/// One entry of an app's `config.launch` section in `appinfo.vdf`
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "rust_bindings.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct SteamLaunchConfig {
+    pub index: u32,
+    pub executable: String,
+    pub arguments: Option<String>,
+    pub working_dir: Option<String>,
+    /// Comma-separated, e.g. "windows" or "linux,macos". Entries without one run everywhere.
+    pub os_list: Option<String>,
+    /// e.g. "default", "vr" or "option1"
+    pub launch_type: Option<String>,
+    pub description: Option<String>,
+    /// Only used on this beta branch of the app
+    pub beta_key: Option<String>,
+}
+
+impl SteamLaunchConfig {
+    pub fn supports(&self, platform: &SteamAppPlatform) -> bool {
+        let os = match platform {
+            SteamAppPlatform::Windows => "windows",
+            SteamAppPlatform::Linux => "linux",
+        };
+
+        self.os_list.as_ref().is_none_or(|os_list| os_list.contains(os))
+    }
+
+    /// The arguments split by Steam's rules, see [`tokenize_steam_arguments`]
+    pub fn argument_list(&self) -> Vec<String> {
+        self.arguments.as_deref().map(tokenize_steam_arguments).unwrap_or_default()
+    }
+
+    /// A game pins its launch config by index or, to survive reordering, by description
+    pub fn matches(&self, launch_config: &str) -> bool {
+        launch_config.parse::<u32>().is_ok_and(|index| index == self.index)
+            || self.description.as_ref().is_some_and(|description| description.eq_ignore_ascii_case(launch_config.trim()))
+    }
+}
+
 pub struct SteamInterface {}
 
 impl SteamInterface {
@@ -43,6 +96,32 @@ impl SteamInterface {
     }
 
     pub fn get_installed_apps(&self, platform_hint: Option<SteamAppPlatform>) -> anyhow::Result<Vec<SteamApp>> {
+        self.find_installed_apps(platform_hint, None)
+    }
+
+    /// Finds a single app, launched with the `config.launch` entry `launch_config` refers to
+    /// (see [`SteamLaunchConfig::matches`]) or the default entry if it is `None`
+    pub fn get_installed_app(&self, steam_id: u32, platform_hint: Option<SteamAppPlatform>, launch_config: Option<&str>) -> anyhow::Result<SteamApp> {
+        self.find_installed_apps(platform_hint, Some((steam_id, launch_config)))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| match launch_config {
+                Some(launch_config) => anyhow::anyhow!("Could not find launch config '{}' of Steam app {}", launch_config, steam_id),
+                None => anyhow::anyhow!("Could not find Steam app with id {}", steam_id),
+            })
+    }
+
+    /// Every `config.launch` entry of an app, ordered by index
+    pub fn get_launch_configs(&self, steam_id: u32) -> anyhow::Result<Option<Vec<SteamLaunchConfig>>> {
+        let steam_dir = steamlocate::SteamDir::locate()?;
+        let app_info = AppInfoDatabase::load_from(steam_dir.path().join("appcache/appinfo.vdf").to_path_buf())?;
+
+        Ok(app_info.app_by_id(steam_id)
+            .map(|app_entry| parse_launch_configs(&app_entry.data["appinfo"]["config.launch"])))
+    }
+
+    /// `only_app` limits the search to one app id, with an optional pinned launch config
+    fn find_installed_apps(&self, platform_hint: Option<SteamAppPlatform>, only_app: Option<(u32, Option<&str>)>) -> anyhow::Result<Vec<SteamApp>> {
         let steam_dir = steamlocate::SteamDir::locate()?;
 
         let mut found_apps = Vec::new();
@@ -53,6 +132,10 @@ impl SteamInterface {
             // println!("Library at {:?}:", library.path());
 
             for app in library.apps().into_iter().filter_map(|l| l.ok()) {
+                if only_app.is_some_and(|(steam_id, _)| steam_id != app.app_id) {
+                    continue;
+                }
+
                 if app.app_id == 1435790 {
                     println!("Found app {}", app.app_id);
                 }
@@ -64,51 +147,18 @@ impl SteamInterface {
                 seen_steam_ids.insert(app.app_id);
 
                 let app_entry = app_info.app_by_id(app.app_id).unwrap();
-                let launch_options = app_entry.data["appinfo"]["config.launch"].as_object();
+                let launch_configs = parse_launch_configs(&app_entry.data["appinfo"]["config.launch"]);
                 let is_vr = app_entry.data["appinfo"]["common.openvrsupport"].parse_i32_and(|i| i == 1);
 
-                if launch_options.is_none() {
+                let pinned_config = only_app.and_then(|(_, launch_config)| launch_config);
+                let Some((launch_config, platform)) = select_launch_config(&launch_configs, platform_hint.as_ref(), pinned_config) else {
                     continue;
-                }
-
-                let launch_options = launch_options.unwrap().values().collect::<Vec<_>>();
-                let win_launch_config = launch_options
-                    .iter()
-                    .find(|l| l["config.oslist"]
-                        .is_string_and(|s| s.contains("windows"))
-                        || l["config.oslist"].is_none());
-                let linux_launch_config = launch_options
-                    .iter()
-                    .find(|l| l["config.oslist"]
-                        .is_string_and(|s| s.contains("linux"))
-                        || l["config.oslist"].is_none());
-
-                if win_launch_config.is_none() && linux_launch_config.is_none() {
-                    continue;
-                }
-
-                let launch_config = match (&platform_hint, linux_launch_config, win_launch_config) {
-                    (Some(SteamAppPlatform::Linux), Some(launch_config), _) => launch_config,
-                    (Some(SteamAppPlatform::Windows), _, Some(launch_config)) => launch_config,
-                    (None, Some(launch_config), _) => launch_config,
-                    (None, None, Some(launch_config)) => launch_config,
-                    _ => continue,
-                };
-
-                let working_dir = launch_config["workingdir"].as_string();
-                let executable = launch_config["executable"].as_string();
-                let arguments = launch_config["arguments"].as_string();
-                let arguments = match arguments {
-                    None => vec![],
-                    Some(args) => args.split(' ').into_iter().map(|s| s.to_string()).collect(),
                 };
 
-                if executable.is_none() {
-                    continue;
-                }
+                let arguments = launch_config.argument_list();
 
-                let executable = executable.unwrap().replace("\\", "/");
-                let working_dir = working_dir.map(|wd| wd.replace("\\", "/"));
+                let executable = launch_config.executable.replace("\\", "/");
+                let working_dir = launch_config.working_dir.as_ref().map(|wd| wd.replace("\\", "/"));
                 let app_install_dir = library.path().join("steamapps/common").join(app.install_dir);
                 let app_exe_path = app_install_dir.join(&executable);
                 let working_dir = working_dir.map(|wd| app_install_dir.join(wd)).unwrap_or(app_install_dir.clone());
@@ -127,10 +177,7 @@ impl SteamInterface {
                     arguments,
                     app_folder: app_install_dir,
                     working_directory: working_dir,
-                    platform: match linux_launch_config.is_some() {
-                        true => SteamAppPlatform::Linux,
-                        false => SteamAppPlatform::Windows,
-                    }
+                    platform,
                 })
             }
         }
@@ -189,4 +236,194 @@ impl SteamInterface {
 
         Ok(versions)
     }
-}
\ No newline at end of file
+
+    /// Picks the Proton version newly imported Windows games should default to: the newest
+    /// numbered Proton release, then Proton Experimental, then whatever else is installed.
+    pub fn guess_proton_version(versions: &[ProtonVersion]) -> Option<&ProtonVersion> {
+        versions.iter()
+            .filter_map(|v| Self::parse_proton_release(&v.name).map(|release| (release, v)))
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, v)| v)
+            .or_else(|| versions.iter().find(|v| v.name.contains("Experimental")))
+            .or_else(|| versions.first())
+    }
+
+    fn parse_proton_release(name: &str) -> Option<Vec<u32>> {
+        let version = name.strip_prefix("Proton ")?.split_whitespace().next()?;
+        version.split('.')
+            .map(|part| part.parse::<u32>().ok())
+            .collect()
+    }
+}
+
+fn parse_launch_configs(launch_section: &KVValue) -> Vec<SteamLaunchConfig> {
+    let Some(entries) = launch_section.as_object() else {
+        return Vec::new();
+    };
+
+    let mut launch_configs = entries.iter()
+        .filter_map(|(index, config)| Some(SteamLaunchConfig {
+            index: index.parse().ok()?,
+            executable: config["executable"].as_string()?.clone(),
+            arguments: config["arguments"].as_string().cloned(),
+            working_dir: config["workingdir"].as_string().cloned(),
+            os_list: config["config.oslist"].as_string().cloned(),
+            launch_type: config["type"].as_string().cloned(),
+            description: config["description"].as_string().cloned(),
+            beta_key: config["config.betakey"].as_string().cloned(),
+        }))
+        .collect::<Vec<_>>();
+    launch_configs.sort_by_key(|config| config.index);
+
+    launch_configs
+}
+
+/// Picks the pinned launch config, or the first one for the preferred platform, favouring
+/// "vr" entries over desktop ones. Entries for beta branches are only used when pinned.
+fn select_launch_config<'a>(launch_configs: &'a [SteamLaunchConfig], platform_hint: Option<&SteamAppPlatform>, pinned: Option<&str>) -> Option<(&'a SteamLaunchConfig, SteamAppPlatform)> {
+    let platforms = match platform_hint {
+        Some(SteamAppPlatform::Linux) => vec![SteamAppPlatform::Linux],
+        Some(SteamAppPlatform::Windows) => vec![SteamAppPlatform::Windows],
+        None => vec![SteamAppPlatform::Linux, SteamAppPlatform::Windows],
+    };
+
+    if let Some(pinned) = pinned {
+        let launch_config = launch_configs.iter().find(|config| config.matches(pinned))?;
+        let platform = platforms.into_iter().find(|platform| launch_config.supports(platform))?;
+        return Some((launch_config, platform));
+    }
+
+    platforms.into_iter().find_map(|platform| {
+        let candidates = launch_configs.iter()
+            .filter(|config| config.beta_key.is_none() && config.supports(&platform))
+            .collect::<Vec<_>>();
+        let launch_config = candidates.iter()
+            .find(|config| config.launch_type.as_deref() == Some("vr"))
+            .or(candidates.first())?;
+
+        Some((*launch_config, platform))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proton(name: &str) -> ProtonVersion {
+        ProtonVersion {
+            steam_id: None,
+            name: name.into(),
+            executable_path: PathBuf::from("/tmp").join(name).join("proton"),
+        }
+    }
+
+    #[test]
+    fn test_guess_prefers_newest_release() {
+        let versions = vec![
+            proton("Proton - Experimental"),
+            proton("Proton 8.0"),
+            proton("Proton 10.0 (Beta)"),
+            proton("Proton 9.0"),
+            proton("GE-Proton9-27"),
+        ];
+
+        let guess = SteamInterface::guess_proton_version(&versions).unwrap();
+        assert_eq!(guess.name, "Proton 10.0 (Beta)");
+    }
+
+    #[test]
+    fn test_guess_falls_back_to_experimental() {
+        let versions = vec![proton("GE-Proton9-27"), proton("Proton - Experimental")];
+
+        let guess = SteamInterface::guess_proton_version(&versions).unwrap();
+        assert_eq!(guess.name, "Proton - Experimental");
+    }
+
+    #[test]
+    fn test_guess_without_versions() {
+        assert!(SteamInterface::guess_proton_version(&[]).is_none());
+    }
+
+    /// Keys like `config.oslist` are nested objects in `appinfo.vdf`
+    fn launch_entry(fields: &[(&str, &str)]) -> KVValue {
+        let mut entry = KVValue::Object(Default::default());
+        for (key, value) in fields {
+            let mut object = &mut entry;
+            let mut parts = key.split('.').peekable();
+            while let Some(part) = parts.next() {
+                let KVValue::Object(map) = object else { unreachable!() };
+                object = match parts.peek() {
+                    Some(_) => map.entry(part.to_string()).or_insert_with(|| KVValue::Object(Default::default())),
+                    None => map.entry(part.to_string()).or_insert(KVValue::String(value.to_string())),
+                };
+            }
+        }
+
+        entry
+    }
+
+    #[test]
+    fn test_select_launch_config() {
+        let launch_section = KVValue::Object([
+            ("0", launch_entry(&[("executable", "Game.exe"), ("type", "default"), ("description", "Play in desktop mode")])),
+            ("1", launch_entry(&[("executable", "Game.exe"), ("arguments", "-vr"), ("type", "vr"), ("description", "Launch in VR"), ("config.oslist", "windows")])),
+            ("2", launch_entry(&[("executable", "GameBeta.exe"), ("type", "vr"), ("config.betakey", "beta")])),
+            ("3", launch_entry(&[("executable", "game.sh"), ("config.oslist", "linux")])),
+            ("4", launch_entry(&[("type", "none")])),
+        ].into_iter().map(|(index, entry)| (index.to_string(), entry)).collect());
+
+        let launch_configs = parse_launch_configs(&launch_section);
+        assert_eq!(launch_configs.iter().map(|c| c.index).collect::<Vec<_>>(), [0, 1, 2, 3]);
+
+        let (config, platform) = select_launch_config(&launch_configs, Some(&SteamAppPlatform::Windows), None).unwrap();
+        assert_eq!((config.index, platform), (1, SteamAppPlatform::Windows));
+
+        let (config, platform) = select_launch_config(&launch_configs, None, None).unwrap();
+        assert_eq!((config.index, platform), (0, SteamAppPlatform::Linux));
+
+        let (config, _) = select_launch_config(&launch_configs, Some(&SteamAppPlatform::Windows), Some("play in desktop mode")).unwrap();
+        assert_eq!(config.index, 0);
+
+        let (config, _) = select_launch_config(&launch_configs, Some(&SteamAppPlatform::Windows), Some("2")).unwrap();
+        assert_eq!(config.executable, "GameBeta.exe");
+
+        assert!(select_launch_config(&launch_configs, Some(&SteamAppPlatform::Linux), Some("1")).is_none());
+        assert!(select_launch_config(&launch_configs, None, Some("7")).is_none());
+    }
+
+    #[test]
+    fn test_launch_configs_from_appinfo() {
+        // A synthetic fixture built by fixtures/generate_appinfo.py, see there for why it is not a real excerpt
+        let app_info = AppInfoDatabase::load_from_bytes(include_bytes!("fixtures/appinfo.vdf").to_vec()).unwrap();
+        let launch_configs = |app_id| parse_launch_configs(&app_info.app_by_id(app_id).unwrap().data["appinfo"]["config.launch"]);
+
+        let sandbox = launch_configs(1001);
+        let (config, _) = select_launch_config(&sandbox, Some(&SteamAppPlatform::Windows), None).unwrap();
+        assert_eq!(config.description.as_deref(), Some("Launch in VR"));
+        assert_eq!(config.argument_list(), ["-vr", "-screen-fullscreen", "0"]);
+        assert_eq!(sandbox[0].argument_list(), ["-window-mode", "exclusive"]);
+
+        let flight_club = launch_configs(1002);
+        let (config, _) = select_launch_config(&flight_club, Some(&SteamAppPlatform::Windows), None).unwrap();
+        assert_eq!(config.executable, r"bin\win64\FlightClub.exe");
+        assert_eq!(config.argument_list(), ["-dataPath", r"C:\Program Files\Flight Club\data\", "+exec", "autoexec vr.cfg", "-title", ""]);
+
+        let (config, platform) = select_launch_config(&flight_club, None, None).unwrap();
+        assert_eq!(platform, SteamAppPlatform::Linux);
+        assert_eq!(config.argument_list(), ["--log-file", "/tmp/flight club.log", "--vulkan"]);
+    }
+
+    #[test]
+    #[ignore = "needs a local Steam installation"]
+    fn test_parse_local_appinfo() {
+        let steam_dir = steamlocate::SteamDir::locate().unwrap();
+        let app_info = AppInfoDatabase::load_from(steam_dir.path().join("appcache/appinfo.vdf")).unwrap();
+        assert!(!app_info.entries.is_empty());
+
+        for appinfo in app_info.entries.iter().filter_map(|entry| entry.data.get("appinfo")) {
+            for config in parse_launch_configs(&appinfo["config.launch"]) {
+                config.argument_list();
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use nix::sys::signal::Signal;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process;
+use uuid::Uuid;
+
+/// Resource usage of a game's scope unit, as reported by systemd
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScopeUsage {
+    pub cpu_time_ms: Option<i64>,
+    pub peak_memory_bytes: Option<i64>,
+}
+
+impl ScopeUsage {
+    /// CPU time only grows, but the peak is tracked by us if systemd is too old to report `MemoryPeak`
+    pub fn merge(&mut self, sample: ScopeUsage) {
+        self.cpu_time_ms = sample.cpu_time_ms.or(self.cpu_time_ms);
+        self.peak_memory_bytes = self.peak_memory_bytes.max(sample.peak_memory_bytes);
+    }
+}
+
+/// Scopes are only used when the system is booted with systemd and `systemd-run` can be found
+pub fn is_available() -> bool {
+    Path::new("/run/systemd/system").exists() && which::which("systemd-run").is_ok()
+}
+
+pub fn unit_name(process_token: &Uuid) -> String {
+    format!("svrl-game-{}.scope", process_token)
+}
+
+/// Rebuilds `command` to run through `systemd-run --user --scope`. The game is exec'd by
+/// `systemd-run`, so it keeps the PID, the environment, the working directory and the output pipes.
+pub fn wrap_command(command: &process::Command, unit: &str) -> process::Command {
+    let command = command.as_std();
+    let mut scope_command = process::Command::new("systemd-run");
+    scope_command.args(["--user", "--scope", "--quiet", "--collect"]);
+    scope_command.arg(format!("--unit={}", unit));
+    scope_command.args(["-p", "CPUAccounting=yes", "-p", "MemoryAccounting=yes"]);
+    scope_command.arg("--");
+    scope_command.arg(command.get_program());
+    scope_command.args(command.get_args());
+
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => scope_command.env(key, value),
+            None => scope_command.env_remove(key),
+        };
+    }
+    if let Some(current_dir) = command.get_current_dir() {
+        scope_command.current_dir(current_dir);
+    }
+
+    scope_command.stdout(Stdio::piped());
+    scope_command.stderr(Stdio::piped());
+
+    scope_command
+}
+
+/// Sends `signal` to every process in the unit
+pub async fn kill(unit: &str, signal: Signal) -> anyhow::Result<()> {
+    systemctl(&["kill", &format!("--signal={}", signal.as_str()), unit]).await?;
+
+    Ok(())
+}
+
+/// Stops the unit, which kills whatever is still running in it
+pub async fn stop(unit: &str) -> anyhow::Result<()> {
+    systemctl(&["stop", unit]).await?;
+
+    Ok(())
+}
+
+pub async fn query_usage(unit: &str) -> anyhow::Result<ScopeUsage> {
+    let output = systemctl(&["show", unit, "-p", "CPUUsageNSec", "-p", "MemoryPeak", "-p", "MemoryCurrent"]).await?;
+
+    Ok(parse_usage(&output))
+}
+
+async fn systemctl(args: &[&str]) -> anyhow::Result<String> {
+    let output = process::Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .output()
+        .await?;
+
+    anyhow::ensure!(
+        output.status.success(),
+        "systemctl {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()
+    );
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn parse_usage(output: &str) -> ScopeUsage {
+    // Properties that are not tracked are reported as "[not set]" or as u64::MAX
+    let properties = output.lines()
+        .filter_map(|line| line.split_once('='))
+        .filter_map(|(key, value)| Some((key, value.trim().parse::<u64>().ok().filter(|v| *v != u64::MAX)? as i64)))
+        .collect::<Vec<_>>();
+    let property = |name: &str| properties.iter().find(|(key, _)| *key == name).map(|(_, value)| *value);
+
+    ScopeUsage {
+        cpu_time_ms: property("CPUUsageNSec").map(|ns| ns / 1_000_000),
+        peak_memory_bytes: property("MemoryPeak").or(property("MemoryCurrent")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_usage() {
+        let usage = parse_usage("CPUUsageNSec=2500000000\nMemoryPeak=[not set]\nMemoryCurrent=1048576\n");
+        assert_eq!(usage, ScopeUsage { cpu_time_ms: Some(2500), peak_memory_bytes: Some(1048576) });
+
+        let usage = parse_usage("CPUUsageNSec=18446744073709551615\nMemoryPeak=4096\nMemoryCurrent=1024\n");
+        assert_eq!(usage, ScopeUsage { cpu_time_ms: None, peak_memory_bytes: Some(4096) });
+    }
+}
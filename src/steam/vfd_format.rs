@@ -42,6 +42,11 @@ impl AppInfoDatabase {
         let mut buffer = vec![];
         file.read_to_end(&mut buffer)?;
 
+        Self::load_from_bytes(buffer)
+    }
+
+    /// Parses the contents of an `appinfo.vdf` file (format version 29, with a string table)
+    pub fn load_from_bytes(buffer: Vec<u8>) -> anyhow::Result<Self> {
         let mut cursor = Cursor::new(buffer);
 
         ensure!(cursor.read_u32::<LittleEndian>()? == 123094057);
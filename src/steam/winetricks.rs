@@ -0,0 +1,137 @@
+use crate::logging::log_channel::LogChannel;
+use crate::models::InstalledVerb;
+use crate::schema::installed_verbs;
+use crate::steam::prefix;
+use crate::steam::steam_interface::ProtonVersion;
+use diesel::{ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl, SqliteConnection};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::process;
+
+/// Verbs are passed to winetricks as arguments, so they are limited to what winetricks uses
+pub fn validate_verbs(verbs: &str) -> Result<(), String> {
+    let invalid = verbs.split_whitespace()
+        .filter(|verb| !verb.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '=' | '.')) || verb.starts_with('-'))
+        .collect::<Vec<_>>();
+
+    match invalid.is_empty() {
+        true => Ok(()),
+        false => Err(format!("Invalid winetricks verbs: {}", invalid.join(", "))),
+    }
+}
+
+/// The verbs of `verbs` (space-separated) that have not been installed into the prefix yet, in order
+pub fn find_missing_verbs(connection: &mut SqliteConnection, prefix_id: &str, verbs: &str) -> QueryResult<Vec<String>> {
+    let installed = installed_verbs::table
+        .filter(installed_verbs::prefix_id.eq(prefix_id))
+        .select(installed_verbs::verb)
+        .load::<String>(connection)?;
+
+    let mut missing = Vec::new();
+    for verb in verbs.split_whitespace() {
+        if !installed.iter().any(|v| v == verb) && !missing.iter().any(|v| v == verb) {
+            missing.push(verb.to_string());
+        }
+    }
+
+    Ok(missing)
+}
+
+pub fn record_installed_verb(connection: &mut SqliteConnection, prefix_id: &str, verb: &str, now: i64) -> QueryResult<()> {
+    diesel::replace_into(installed_verbs::table)
+        .values(InstalledVerb {
+            prefix_id: prefix_id.to_string(),
+            verb: verb.to_string(),
+            installed_at: now,
+        })
+        .execute(connection)?;
+
+    Ok(())
+}
+
+/// Called whenever the prefix is replaced, so the verbs are installed again on the next launch
+pub fn forget_installed_verbs(connection: &mut SqliteConnection, prefix_id: &str) -> QueryResult<()> {
+    diesel::delete(installed_verbs::table.filter(installed_verbs::prefix_id.eq(prefix_id)))
+        .execute(connection)?;
+
+    Ok(())
+}
+
+/// Lets Proton create the prefix, so winetricks does not set up a plain wine prefix in its place
+pub async fn initialize_prefix(proton: &ProtonVersion, prefix_id: &str, logger: Arc<Mutex<LogChannel>>) -> anyhow::Result<()> {
+    let compat_data_path = prefix::compat_data_path(prefix_id)?;
+    if compat_data_path.join("pfx").exists() {
+        return Ok(());
+    }
+
+    println!("Creating the Proton prefix {}...", prefix_id);
+    std::fs::create_dir_all(&compat_data_path)?;
+    let steam_home = steamlocate::SteamDir::locate()?;
+    let mut command = process::Command::new("python3");
+    command.arg(&proton.executable_path)
+        .args(["run", "wineboot", "--init"])
+        .env("STEAM_COMPAT_DATA_PATH", &compat_data_path)
+        .env("STEAM_COMPAT_CLIENT_INSTALL_PATH", steam_home.path());
+
+    run_logged(command, logger, "Proton could not create the prefix").await
+}
+
+/// Installs a single verb with the wine of the game's Proton version
+pub async fn install_verb(proton: &ProtonVersion, prefix_id: &str, verb: &str, logger: Arc<Mutex<LogChannel>>) -> anyhow::Result<()> {
+    let winetricks = which::which("winetricks")
+        .map_err(|_| anyhow::anyhow!("winetricks is not installed"))?;
+    let wine = proton.find_wine_binary("wine")
+        .ok_or_else(|| anyhow::anyhow!("Could not find wine in {}", proton.name))?;
+    let wineserver = proton.find_wine_binary("wineserver")
+        .ok_or_else(|| anyhow::anyhow!("Could not find wineserver in {}", proton.name))?;
+    let wine_prefix: PathBuf = prefix::compat_data_path(prefix_id)?.join("pfx");
+
+    println!("Installing winetricks verb '{}' into prefix {}...", verb, prefix_id);
+    let mut command = process::Command::new(winetricks);
+    command.args(["--unattended", verb])
+        .env("WINEPREFIX", wine_prefix)
+        .env("WINE", wine)
+        .env("WINESERVER", wineserver);
+
+    run_logged(command, logger, &format!("winetricks could not install '{}'", verb)).await
+}
+
+async fn run_logged(mut command: process::Command, logger: Arc<Mutex<LogChannel>>, error_message: &str) -> anyhow::Result<()> {
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::piped());
+
+    let mut child = command.spawn()?;
+    LogChannel::connect_tokio(logger, &mut child);
+    let status = child.wait().await?;
+    anyhow::ensure!(status.success(), "{} ({})", error_message, status);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::open_test_connection;
+
+    #[test]
+    fn test_missing_verbs() {
+        let connection = &mut open_test_connection();
+        record_installed_verb(connection, "123", "vcrun2019", 1000).unwrap();
+        record_installed_verb(connection, "456", "dotnet48", 1000).unwrap();
+
+        let missing = find_missing_verbs(connection, "123", "vcrun2019 d3dcompiler_47 dotnet48 d3dcompiler_47").unwrap();
+        assert_eq!(missing, ["d3dcompiler_47", "dotnet48"]);
+
+        forget_installed_verbs(connection, "123").unwrap();
+        assert_eq!(find_missing_verbs(connection, "123", "vcrun2019").unwrap(), ["vcrun2019"]);
+    }
+
+    #[test]
+    fn test_validate_verbs() {
+        assert!(validate_verbs("vcrun2019  d3dcompiler_47 win10 sound=pulse").is_ok());
+        assert!(validate_verbs("").is_ok());
+        assert!(validate_verbs("vcrun2019 --force").is_err());
+        assert!(validate_verbs("$(rm -rf /)").is_err());
+    }
+}